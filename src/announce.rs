@@ -0,0 +1,334 @@
+//! Parsing and building HTTP tracker announce queries (BEP 3, BEP 23).
+
+use crate::errors::{AnnounceError, UrlDecodeError};
+use crate::url::{decode_20_bytes, percent_decode, percent_encode};
+use crate::PeerId;
+use std::str::FromStr;
+
+/// The `event` parameter of an announce.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Event {
+    /// The first announce for a torrent.
+    Started,
+    /// The client is shutting down gracefully.
+    Stopped,
+    /// The download finished.
+    Completed,
+    /// The client stopped downloading, but keeps seeding what it has: a partial seed (BEP 21).
+    Paused,
+}
+
+impl Event {
+    /// The value used in announce queries.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Started => "started",
+            Self::Stopped => "stopped",
+            Self::Completed => "completed",
+            Self::Paused => "paused",
+        }
+    }
+}
+
+/// A parsed HTTP tracker announce query.
+///
+/// ```
+/// # use tdyne_peer_id::PeerId;
+/// # use tdyne_peer_id::announce::{AnnounceRequest, Event};
+/// let query = "info_hash=%12%34V%78%9A%BC%DE%F1%23Eg%89%AB%CD%EF%124Vx%9A\
+///              &peer_id=-TR4050-k8hj0wgej6ch&port=51413&uploaded=0&downloaded=0\
+///              &left=1024&event=started&key=1f2e3d4c&compact=1&numwant=80";
+/// let request = AnnounceRequest::parse(query).unwrap();
+/// assert_eq!(request.peer_id, PeerId::from(b"-TR4050-k8hj0wgej6ch"));
+/// assert_eq!(request.peer_id.client_info().unwrap().name, "Transmission");
+/// assert_eq!(request.port, 51413);
+/// assert_eq!(request.event, Some(Event::Started));
+/// assert_eq!(request.numwant, Some(80));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AnnounceRequest {
+    /// `info_hash`, the torrent's info-hash.
+    pub info_hash: [u8; 20],
+    /// `peer_id`.
+    pub peer_id: PeerId,
+    /// `port` the client listens on.
+    pub port: u16,
+    /// `uploaded` bytes since the `started` event.
+    pub uploaded: u64,
+    /// `downloaded` bytes since the `started` event.
+    pub downloaded: u64,
+    /// `left`, bytes the client still has to download.
+    pub left: u64,
+    /// `event`, `None` for regular announces. An empty `event` or `event=empty` is `None` too.
+    pub event: Option<Event>,
+    /// `key`, an opaque value identifying the client across IP changes, percent-decoded.
+    pub key: Option<Vec<u8>>,
+    /// `compact`, if present: whether the client accepts compact peer lists (BEP 23).
+    pub compact: Option<bool>,
+    /// `numwant`, how many peers the client wants.
+    pub numwant: Option<u32>,
+}
+
+impl AnnounceRequest {
+    /// Parses a query string, with or without the leading `?`. Unknown parameters are ignored.
+    /// If a parameter is repeated, the last value wins.
+    ///
+    /// `info_hash` and `peer_id` are decoded as tolerantly as [`PeerId::from_url_encoded`]
+    /// does, since the query comes straight from a client. The query doesn't have to be valid
+    /// UTF-8.
+    pub fn parse(query: impl AsRef<[u8]>) -> Result<Self, AnnounceError> {
+        let query = query.as_ref();
+        let query = query.strip_prefix(b"?").unwrap_or(query);
+
+        let mut info_hash = None;
+        let mut peer_id = None;
+        let mut port = None;
+        let mut uploaded = None;
+        let mut downloaded = None;
+        let mut left = None;
+        let mut event = None;
+        let mut key = None;
+        let mut compact = None;
+        let mut numwant = None;
+
+        for pair in query.split(|&b| b == b'&').filter(|pair| !pair.is_empty()) {
+            let (name, value) = match pair.iter().position(|&b| b == b'=') {
+                Some(i) => (&pair[..i], &pair[i + 1..]),
+                None => (pair, &[][..]),
+            };
+            match name {
+                b"info_hash" => info_hash = Some(decode_20("info_hash", value)?),
+                b"peer_id" => peer_id = Some(PeerId(decode_20("peer_id", value)?)),
+                b"port" => port = Some(number("port", value)?),
+                b"uploaded" => uploaded = Some(number("uploaded", value)?),
+                b"downloaded" => downloaded = Some(number("downloaded", value)?),
+                b"left" => left = Some(number("left", value)?),
+                b"event" => {
+                    event = match value {
+                        b"started" => Some(Event::Started),
+                        b"stopped" => Some(Event::Stopped),
+                        b"completed" => Some(Event::Completed),
+                        b"paused" => Some(Event::Paused),
+                        b"" | b"empty" => None,
+                        _ => return Err(invalid("event", value)),
+                    }
+                }
+                b"key" => key = Some(decode("key", value)?),
+                b"compact" => {
+                    compact = match value {
+                        b"1" => Some(true),
+                        b"0" => Some(false),
+                        _ => return Err(invalid("compact", value)),
+                    }
+                }
+                b"numwant" => numwant = Some(number("numwant", value)?),
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            info_hash: info_hash.ok_or(AnnounceError::Missing("info_hash"))?,
+            peer_id: peer_id.ok_or(AnnounceError::Missing("peer_id"))?,
+            port: port.ok_or(AnnounceError::Missing("port"))?,
+            uploaded: uploaded.ok_or(AnnounceError::Missing("uploaded"))?,
+            downloaded: downloaded.ok_or(AnnounceError::Missing("downloaded"))?,
+            left: left.ok_or(AnnounceError::Missing("left"))?,
+            event,
+            key,
+            compact,
+            numwant,
+        })
+    }
+
+    /// Builds the query string, without the leading `?`. Optional parameters are only included
+    /// if they are set.
+    ///
+    /// ```
+    /// # use tdyne_peer_id::PeerId;
+    /// # use tdyne_peer_id::announce::AnnounceRequest;
+    /// let request = AnnounceRequest {
+    ///     info_hash: [0xab; 20],
+    ///     peer_id: PeerId::from(b"-TR4050-k8hj0wgej6ch"),
+    ///     port: 51413,
+    ///     uploaded: 0,
+    ///     downloaded: 0,
+    ///     left: 0,
+    ///     event: None,
+    ///     key: None,
+    ///     compact: Some(true),
+    ///     numwant: None,
+    /// };
+    /// let query = request.to_query_string();
+    /// assert!(query.starts_with("info_hash=%AB%AB"));
+    /// assert_eq!(AnnounceRequest::parse(&query), Ok(request));
+    /// ```
+    pub fn to_query_string(&self) -> String {
+        let mut query = String::with_capacity(200);
+        query.push_str("info_hash=");
+        percent_encode(&self.info_hash, &mut query);
+        query.push_str("&peer_id=");
+        percent_encode(&self.peer_id.0, &mut query);
+        query.push_str(&format!(
+            "&port={}&uploaded={}&downloaded={}&left={}",
+            self.port, self.uploaded, self.downloaded, self.left
+        ));
+        if let Some(event) = self.event {
+            query.push_str("&event=");
+            query.push_str(event.as_str());
+        }
+        if let Some(key) = &self.key {
+            query.push_str("&key=");
+            percent_encode(key, &mut query);
+        }
+        if let Some(compact) = self.compact {
+            query.push_str(if compact { "&compact=1" } else { "&compact=0" });
+        }
+        if let Some(numwant) = self.numwant {
+            query.push_str(&format!("&numwant={}", numwant));
+        }
+        query
+    }
+}
+
+fn invalid(param: &'static str, value: &[u8]) -> AnnounceError {
+    let value = percent_decode(value).unwrap_or_else(|_| value.to_vec());
+    AnnounceError::Invalid {
+        param,
+        value: String::from_utf8_lossy(&value).into_owned(),
+    }
+}
+
+fn decode(param: &'static str, value: &[u8]) -> Result<Vec<u8>, AnnounceError> {
+    percent_decode(value).map_err(|error| AnnounceError::Decode { param, error })
+}
+
+fn decode_20(param: &'static str, value: &[u8]) -> Result<[u8; 20], AnnounceError> {
+    decode_20_bytes(value).map_err(|error| match error {
+        UrlDecodeError::Length(e) => AnnounceError::Length { param, len: e.0 },
+        error => AnnounceError::Decode { param, error },
+    })
+}
+
+fn number<T: FromStr>(param: &'static str, value: &[u8]) -> Result<T, AnnounceError> {
+    // `FromStr` for integers accepts a leading `+`, which clients never send
+    if value.is_empty() || !value.iter().all(u8::is_ascii_digit) {
+        return Err(invalid(param, value));
+    }
+    std::str::from_utf8(value)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| invalid(param, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const INFO_HASH: &str = "%124Vx%9A%BC%DE%F1%23Eg%89%AB%CD%EF%124Vx%9A";
+
+    fn query(extra: &str) -> String {
+        format!(
+            "info_hash={}&peer_id=-qB46A0-k8hj0wgej6ch&port=6881&uploaded=10&downloaded=20\
+             &left=30{}",
+            INFO_HASH, extra
+        )
+    }
+
+    #[test]
+    fn minimal() {
+        let request = AnnounceRequest::parse(format!("?{}", query(""))).unwrap();
+        assert_eq!(
+            request,
+            AnnounceRequest {
+                info_hash: *b"\x12\x34\x56\x78\x9a\xbc\xde\xf1\x23\x45\x67\x89\xab\xcd\xef\x12\x34\x56\x78\x9a",
+                peer_id: PeerId::from(b"-qB46A0-k8hj0wgej6ch"),
+                port: 6881,
+                uploaded: 10,
+                downloaded: 20,
+                left: 30,
+                event: None,
+                key: None,
+                compact: None,
+                numwant: None,
+            }
+        );
+        assert_eq!(
+            AnnounceRequest::parse(request.to_query_string()),
+            Ok(request)
+        );
+    }
+
+    #[test]
+    fn optional() {
+        let request =
+            AnnounceRequest::parse(query("&event=empty&key=a%20b&compact=0&numwant=0&foo"))
+                .unwrap();
+        assert_eq!(request.event, None);
+        assert_eq!(request.key.as_deref(), Some(&b"a b"[..]));
+        assert_eq!(request.compact, Some(false));
+        assert_eq!(request.numwant, Some(0));
+        assert_eq!(
+            AnnounceRequest::parse(request.to_query_string()),
+            Ok(request)
+        );
+
+        // partial seeds (BEP 21)
+        let request = AnnounceRequest::parse(query("&event=paused")).unwrap();
+        assert_eq!(request.event, Some(Event::Paused));
+        assert_eq!(
+            AnnounceRequest::parse(request.to_query_string()),
+            Ok(request)
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            AnnounceRequest::parse("peer_id=-qB46A0-k8hj0wgej6ch"),
+            Err(AnnounceError::Missing("info_hash"))
+        );
+        assert_eq!(
+            AnnounceRequest::parse(query("&port=65536")),
+            Err(AnnounceError::Invalid {
+                param: "port",
+                value: "65536".to_owned()
+            })
+        );
+        assert_eq!(
+            AnnounceRequest::parse(query("&left=-1")),
+            Err(AnnounceError::Invalid {
+                param: "left",
+                value: "-1".to_owned()
+            })
+        );
+        assert_eq!(
+            AnnounceRequest::parse(query("&event=resumed")),
+            Err(AnnounceError::Invalid {
+                param: "event",
+                value: "resumed".to_owned()
+            })
+        );
+        assert_eq!(
+            AnnounceRequest::parse(query("&peer_id=-qB46A0-")),
+            Err(AnnounceError::Length {
+                param: "peer_id",
+                len: 8
+            })
+        );
+        assert_eq!(
+            AnnounceRequest::parse(query("&info_hash=%12%3")),
+            Err(AnnounceError::Decode {
+                param: "info_hash",
+                error: UrlDecodeError::TruncatedEscape { position: 3 }
+            })
+        );
+        assert_eq!(
+            AnnounceRequest::parse(query("&info_hash=%12")),
+            Err(AnnounceError::Length {
+                param: "info_hash",
+                len: 1
+            })
+        );
+    }
+}
@@ -0,0 +1,300 @@
+//! Bencode support: peer IDs as bencoded byte strings, and extracting peer IDs from tracker
+//! announce responses.
+//!
+//! Only the bits of bencode needed for that are implemented, there is no general purpose
+//! bencode value type here.
+
+use crate::errors::BencodeError;
+use crate::PeerId;
+
+/// Lists and dictionaries nested deeper than this are rejected, so that malicious input can't
+/// overflow the stack. Tracker responses are never nested more than a few levels deep.
+const MAX_DEPTH: usize = 64;
+
+impl PeerId {
+    /// Encodes the peer ID as a bencoded byte string, `20:` followed by the 20 bytes.
+    ///
+    /// ```
+    /// # use tdyne_peer_id::PeerId;
+    /// let peer_id = PeerId::from(b"-TR4050-k8hj0wgej6ch");
+    /// assert_eq!(&peer_id.to_bencode(), b"20:-TR4050-k8hj0wgej6ch");
+    /// ```
+    pub fn to_bencode(&self) -> [u8; 23] {
+        let mut bencoded = [0; 23];
+        bencoded[..3].copy_from_slice(b"20:");
+        bencoded[3..].copy_from_slice(&self.0);
+        bencoded
+    }
+
+    /// Decodes a peer ID from a bencoded byte string. The input must contain the byte string
+    /// and nothing else.
+    ///
+    /// ```
+    /// # use tdyne_peer_id::PeerId;
+    /// # use tdyne_peer_id::errors::{BadPeerIdLengthError, BencodeError};
+    /// let peer_id = PeerId::from_bencode(b"20:-TR4050-k8hj0wgej6ch").unwrap();
+    /// assert_eq!(peer_id, PeerId::from(b"-TR4050-k8hj0wgej6ch"));
+    ///
+    /// let error = PeerId::from_bencode(b"8:-TR4050-").unwrap_err();
+    /// let length = BadPeerIdLengthError(8);
+    /// assert_eq!(error, BencodeError::PeerIdLength { position: 0, error: length });
+    /// ```
+    pub fn from_bencode(input: &[u8]) -> Result<Self, BencodeError> {
+        let mut parser = Parser { input, pos: 0 };
+        let peer_id = parser.peer_id()?;
+        parser.finish()?;
+        Ok(peer_id)
+    }
+}
+
+/// Extracts all peer IDs from a non-compact HTTP tracker announce response, in the order they
+/// appear in the `peers` list.
+///
+/// Peers without a `peer id` key (trackers omit it when asked with `no_peer_id=1`) are skipped.
+/// Compact responses, where `peers` is a byte string, have no peer IDs, so the result is empty.
+/// A `peer id` that isn't 20 bytes long is an error.
+///
+/// ```
+/// # use tdyne_peer_id::PeerId;
+/// # use tdyne_peer_id::bencode;
+/// let response = b"d8:intervali1800e5:peersld2:ip9:127.0.0.17:peer id20:-TR4050-k8hj0wgej6ch\
+///                  4:porti6881eeee";
+/// let peer_ids = bencode::peer_ids_from_announce(response).unwrap();
+/// assert_eq!(peer_ids, vec![PeerId::from(b"-TR4050-k8hj0wgej6ch")]);
+/// ```
+pub fn peer_ids_from_announce(response: &[u8]) -> Result<Vec<PeerId>, BencodeError> {
+    let mut parser = Parser {
+        input: response,
+        pos: 0,
+    };
+    let mut peer_ids = Vec::new();
+
+    parser.expect(b'd', "a dictionary")?;
+    while !parser.end_of_container()? {
+        let key = parser.byte_string()?;
+        if key != b"peers" || parser.peek()? != b'l' {
+            parser.skip_value(1)?;
+            continue;
+        }
+
+        parser.pos += 1;
+        while !parser.end_of_container()? {
+            parser.expect(b'd', "a peer dictionary")?;
+            while !parser.end_of_container()? {
+                if parser.byte_string()? == b"peer id" {
+                    peer_ids.push(parser.peer_id()?);
+                } else {
+                    parser.skip_value(3)?;
+                }
+            }
+        }
+    }
+    parser.finish()?;
+
+    Ok(peer_ids)
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Result<u8, BencodeError> {
+        self.input
+            .get(self.pos)
+            .copied()
+            .ok_or(BencodeError::UnexpectedEnd)
+    }
+
+    fn unexpected(&self, expected: &'static str) -> BencodeError {
+        BencodeError::Unexpected {
+            position: self.pos,
+            expected,
+        }
+    }
+
+    fn expect(&mut self, byte: u8, expected: &'static str) -> Result<(), BencodeError> {
+        if self.peek()? != byte {
+            return Err(self.unexpected(expected));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    /// Consumes the `e` closing a list or a dictionary, if it's next.
+    fn end_of_container(&mut self) -> Result<bool, BencodeError> {
+        let end = self.peek()? == b'e';
+        if end {
+            self.pos += 1;
+        }
+        Ok(end)
+    }
+
+    /// Reads decimal digits up to `terminator`, which is consumed.
+    fn number(&mut self, terminator: u8) -> Result<usize, BencodeError> {
+        let start = self.pos;
+        let mut n: usize = 0;
+        loop {
+            let b = self.peek()?;
+            if b == terminator && self.pos > start {
+                self.pos += 1;
+                return Ok(n);
+            }
+            if !b.is_ascii_digit() {
+                return Err(self.unexpected("a digit"));
+            }
+            n = n
+                .checked_mul(10)
+                .and_then(|n| n.checked_add(usize::from(b - b'0')))
+                .ok_or_else(|| self.unexpected("a shorter number"))?;
+            self.pos += 1;
+        }
+    }
+
+    fn byte_string(&mut self) -> Result<&'a [u8], BencodeError> {
+        if !self.peek()?.is_ascii_digit() {
+            return Err(self.unexpected("a byte string"));
+        }
+        let len = self.number(b':')?;
+        let bytes = self
+            .input
+            .get(self.pos..)
+            .and_then(|rest| rest.get(..len))
+            .ok_or(BencodeError::UnexpectedEnd)?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn peer_id(&mut self) -> Result<PeerId, BencodeError> {
+        let position = self.pos;
+        let bytes = self.byte_string()?;
+        PeerId::try_from(bytes).map_err(|error| BencodeError::PeerIdLength { position, error })
+    }
+
+    fn skip_value(&mut self, depth: usize) -> Result<(), BencodeError> {
+        match self.peek()? {
+            b'i' => {
+                self.pos += 1;
+                if self.peek()? == b'-' {
+                    self.pos += 1;
+                }
+                self.number(b'e')?;
+            }
+            b'0'..=b'9' => {
+                self.byte_string()?;
+            }
+            b'l' | b'd' => {
+                if depth >= MAX_DEPTH {
+                    return Err(BencodeError::TooDeep { position: self.pos });
+                }
+                let dictionary = self.peek()? == b'd';
+                self.pos += 1;
+                while !self.end_of_container()? {
+                    if dictionary {
+                        self.byte_string()?;
+                    }
+                    self.skip_value(depth + 1)?;
+                }
+            }
+            _ => return Err(self.unexpected("a bencoded value")),
+        }
+        Ok(())
+    }
+
+    fn finish(&self) -> Result<(), BencodeError> {
+        if self.pos != self.input.len() {
+            return Err(BencodeError::TrailingData { position: self.pos });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::BadPeerIdLengthError;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn from_bencode_errors() {
+        assert_eq!(
+            PeerId::from_bencode(b"20:-TR4050-k8hj0wgej6chx"),
+            Err(BencodeError::TrailingData { position: 23 })
+        );
+        assert_eq!(
+            PeerId::from_bencode(b"20:-TR4050-"),
+            Err(BencodeError::UnexpectedEnd)
+        );
+        assert_eq!(
+            PeerId::from_bencode(b"i20e"),
+            Err(BencodeError::Unexpected {
+                position: 0,
+                expected: "a byte string"
+            })
+        );
+        assert_eq!(
+            PeerId::from_bencode(b"99999999999999999999999:"),
+            Err(BencodeError::Unexpected {
+                position: 19,
+                expected: "a shorter number"
+            })
+        );
+    }
+
+    #[test]
+    fn announce() {
+        let response = b"d8:completei1e10:incompletei-2e8:intervali1800e5:peersl\
+            d2:ip9:127.0.0.17:peer id20:-TR4050-k8hj0wgej6ch4:porti6881ee\
+            d2:ip3:::14:porti1ee\
+            d4:infold1:xi1eee7:peer id20:-qB46A0-k8hj0wgej6che\
+            ee";
+        let peer_ids = peer_ids_from_announce(response).unwrap();
+        assert_eq!(
+            peer_ids,
+            vec![
+                PeerId::from(b"-TR4050-k8hj0wgej6ch"),
+                PeerId::from(b"-qB46A0-k8hj0wgej6ch"),
+            ]
+        );
+    }
+
+    #[test]
+    fn compact_announce() {
+        let response = b"d8:intervali1800e5:peers6:\x7f\x00\x00\x01\x1a\xe1e";
+        assert_eq!(peer_ids_from_announce(response), Ok(vec![]));
+    }
+
+    #[test]
+    fn announce_errors() {
+        let response = b"d5:peersld7:peer id3:abceee";
+        assert_eq!(
+            peer_ids_from_announce(response),
+            Err(BencodeError::PeerIdLength {
+                position: 19,
+                error: BadPeerIdLengthError(3)
+            })
+        );
+
+        assert_eq!(
+            peer_ids_from_announce(b"d5:peersld7:peer id20:-TR4050-k8hj0wgej6ch"),
+            Err(BencodeError::UnexpectedEnd)
+        );
+
+        let mut deep = b"d3:fooi1e4:deep".to_vec();
+        deep.extend([b'l'; 100]);
+        deep.extend([b'e'; 101]);
+        assert_eq!(
+            peer_ids_from_announce(&deep),
+            Err(BencodeError::TooDeep { position: 78 })
+        );
+
+        assert_eq!(
+            peer_ids_from_announce(b"le"),
+            Err(BencodeError::Unexpected {
+                position: 0,
+                expected: "a dictionary"
+            })
+        );
+    }
+}
@@ -0,0 +1,132 @@
+//! Comparing the peer ID a tracker reported for an address with the one the peer sent in its
+//! handshake.
+//!
+//! The two should be identical. When they aren't, the address is likely shared by several peers
+//! behind a NAT, the peer is spoofing its ID, or the tracker rewrote it.
+
+use crate::style::Style;
+use crate::PeerId;
+
+/// How the tracker-reported peer ID relates to the handshake one, see [`compare`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Consistency {
+    /// Both peer IDs are the same.
+    Exact,
+    /// Both peer IDs have the same style and the same prefix, including the client version, but
+    /// different random suffixes. Typically the client restarted, or several instances of it
+    /// share an address.
+    SameClient,
+    /// The prefixes differ, or at least one of the peer IDs has no recognisable [`Style`] to
+    /// compare prefixes by.
+    DifferentClient,
+    /// The tracker reported a blank peer ID, all zeros or one byte repeated. Some trackers do that
+    /// to hide peer IDs, there is nothing to compare.
+    Obfuscated,
+}
+
+/// Compares the peer ID a tracker reported for an address with the one the peer at that address
+/// sent in its handshake.
+///
+/// ```
+/// # use tdyne_peer_id::PeerId;
+/// # use tdyne_peer_id::consistency::{compare, Consistency};
+/// let handshake = PeerId::from(b"-TR4050-k8hj0wgej6ch");
+///
+/// assert_eq!(compare(&handshake, &handshake), Consistency::Exact);
+///
+/// let tracker = PeerId::from(b"-TR4050-0123456789ab");
+/// assert_eq!(compare(&tracker, &handshake), Consistency::SameClient);
+///
+/// let tracker = PeerId::from(b"-TR4040-k8hj0wgej6ch");
+/// assert_eq!(compare(&tracker, &handshake), Consistency::DifferentClient);
+///
+/// let tracker = PeerId::from(&[0; 20]);
+/// assert_eq!(compare(&tracker, &handshake), Consistency::Obfuscated);
+/// ```
+pub fn compare(tracker: &PeerId, handshake: &PeerId) -> Consistency {
+    if tracker == handshake {
+        return Consistency::Exact;
+    }
+    if tracker.0.iter().all(|&b| b == tracker.0[0]) {
+        return Consistency::Obfuscated;
+    }
+    match (prefix_len(tracker), prefix_len(handshake)) {
+        (Some(a), Some(b)) if a == b && tracker.0[..a] == handshake.0[..b] => {
+            Consistency::SameClient
+        }
+        _ => Consistency::DifferentClient,
+    }
+}
+
+/// Length of the non-random part of the peer ID, if it has a style.
+fn prefix_len(peer_id: &PeerId) -> Option<usize> {
+    let suffix_len = match peer_id.style()? {
+        Style::Azureus(azureus) => azureus.suffix().len(),
+        Style::Shadow(shadow) => shadow.suffix().len(),
+        Style::Mainline(mainline) => mainline.suffix().len(),
+    };
+    Some(peer_id.0.len() - suffix_len)
+}
+
+impl PeerId {
+    /// Compares the peer ID, as reported by a tracker, with the one received in a handshake.
+    /// Shorthand for [`consistency::compare`].
+    ///
+    /// [`consistency::compare`]: crate::consistency::compare
+    pub fn consistency_with(&self, handshake: &PeerId) -> Consistency {
+        compare(self, handshake)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn compare() {
+        let cases: &[(&[u8; 20], &[u8; 20], Consistency)] = &[
+            (
+                b"M7-4-3--k8hj0wgej6ch",
+                b"M7-4-3--0123456789ab",
+                Consistency::SameClient,
+            ),
+            (
+                b"M7-4-3--k8hj0wgej6ch",
+                b"M7-4-30-k8hj0wgej6ch",
+                Consistency::DifferentClient,
+            ),
+            (
+                b"T03I-----k8hj0wgej6c",
+                b"T03I-----0123456789a",
+                Consistency::SameClient,
+            ),
+            (
+                b"-TR4050-k8hj0wgej6ch",
+                b"-qB4050-k8hj0wgej6ch",
+                Consistency::DifferentClient,
+            ),
+            (
+                b"\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff",
+                b"-TR4050-k8hj0wgej6ch",
+                Consistency::Obfuscated,
+            ),
+            // no style, nothing to compare prefixes by
+            (
+                b"\x00\x01\x02\x03\x04\x05\x06\x07\x08\x09\x0a\x0b\x0c\x0d\x0e\x0f\x10\x11\x12\x13",
+                b"\x00\x01\x02\x03\x04\x05\x06\x07\x08\x09\x0a\x0b\x0c\x0d\x0e\x0f\x10\x11\x12\x14",
+                Consistency::DifferentClient,
+            ),
+            // a blank peer ID in the handshake is the peer's own choice
+            (
+                b"-TR4050-k8hj0wgej6ch",
+                &[0; 20],
+                Consistency::DifferentClient,
+            ),
+        ];
+        for (tracker, handshake, expected) in cases {
+            let actual = PeerId::from(*tracker).consistency_with(&PeerId::from(*handshake));
+            assert_eq!(actual, *expected, "{:?} vs {:?}", tracker, handshake);
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::version::ClientVersion;
 use std::fmt;
 
 /// Returned when provided byte slice length is not equal to 20 bytes. Includes the
@@ -18,4 +19,461 @@ impl fmt::Display for BadPeerIdLengthError {
     }
 }
 
-impl std::error::Error for BadPeerIdLengthError {}
\ No newline at end of file
+impl std::error::Error for BadPeerIdLengthError {}
+
+/// Returned when a string can't be parsed as a [`PeerId`](crate::PeerId): it's neither hex,
+/// base64, nor a valid escaped form of exactly 20 bytes. Includes the offending string.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParsePeerIdError(
+    /// the string that failed to parse
+    pub String,
+);
+
+impl fmt::Display for ParsePeerIdError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Invalid peer ID {:?}, expected 40 hex digits, 27 or 28 base64 characters, or an \
+             escaped string of 20 bytes",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for ParsePeerIdError {}
+
+/// Returned when a string can't be parsed as a [`ClientVersion`]. Includes the offending string.
+///
+/// [`ClientVersion`]: crate::version::ClientVersion
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseVersionError(
+    /// the string that failed to parse
+    pub String,
+);
+
+impl fmt::Display for ParseVersionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Invalid client version {:?}, expected up to four numbers separated by dots, \
+             optionally followed by -beta or -dev",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for ParseVersionError {}
+
+/// Returned when a string can't be parsed as a [`VersionReq`]. Includes the offending
+/// comparator.
+///
+/// [`VersionReq`]: crate::version::VersionReq
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseVersionReqError(
+    /// the comparator that failed to parse
+    pub String,
+);
+
+impl fmt::Display for ParseVersionReqError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Invalid version comparator {:?}, expected an operator (=, >, >=, <, <=) followed by \
+             a version",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for ParseVersionReqError {}
+
+/// Returned when an Azureus-style prefix can't be built from the provided client code and
+/// version.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum BadPrefixError {
+    /// Client code must be exactly two characters long. Includes the length in bytes.
+    CodeLength(usize),
+    /// Version must be exactly four characters long. Includes the length in bytes.
+    VersionLength(usize),
+    /// Client code and version must be ASCII alphanumeric. Includes the offending character.
+    NotAlphanumeric(char),
+    /// The client's version encoding can't represent this version exactly.
+    UnencodableVersion(ClientVersion),
+}
+
+impl fmt::Display for BadPrefixError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::CodeLength(len) => write!(
+                f,
+                "Invalid client code length, expected 2 bytes, got {} bytes",
+                len
+            ),
+            Self::VersionLength(len) => write!(
+                f,
+                "Invalid client version length, expected 4 bytes, got {} bytes",
+                len
+            ),
+            Self::NotAlphanumeric(c) => write!(
+                f,
+                "Invalid character {:?} in the prefix, only ASCII letters and digits are allowed",
+                c
+            ),
+            Self::UnencodableVersion(version) => write!(
+                f,
+                "Version {} can't be encoded in the prefix with the client's version encoding",
+                version
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BadPrefixError {}
+
+/// Returned when bencoded data can't be decoded. Positions are byte offsets into the input.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum BencodeError {
+    /// The input ended in the middle of a value.
+    UnexpectedEnd,
+    /// Found something other than what the grammar allows at this position.
+    Unexpected {
+        /// where the offending byte is
+        position: usize,
+        /// what was expected instead, for example `"a byte string"`
+        expected: &'static str,
+    },
+    /// A peer ID byte string is not exactly 20 bytes long.
+    PeerIdLength {
+        /// where the byte string starts, including its length prefix
+        position: usize,
+        /// the underlying length error
+        error: BadPeerIdLengthError,
+    },
+    /// There is more input after the value.
+    TrailingData {
+        /// where the extra input starts
+        position: usize,
+    },
+    /// Lists and dictionaries are nested too deeply.
+    TooDeep {
+        /// where the list or dictionary that went over the limit starts
+        position: usize,
+    },
+}
+
+impl fmt::Display for BencodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnexpectedEnd => write!(f, "Invalid bencode, unexpected end of input"),
+            Self::Unexpected { position, expected } => write!(
+                f,
+                "Invalid bencode at byte {}, expected {}",
+                position, expected
+            ),
+            Self::PeerIdLength { position, error } => {
+                write!(f, "Invalid peer ID at byte {}: {}", position, error)
+            }
+            Self::TrailingData { position } => {
+                write!(f, "Invalid bencode, unexpected data at byte {}", position)
+            }
+            Self::TooDeep { position } => write!(
+                f,
+                "Invalid bencode at byte {}, lists and dictionaries are nested too deeply",
+                position
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BencodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::PeerIdLength { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Returned when a percent-encoded peer ID can't be decoded. Positions are byte offsets into
+/// the input.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum UrlDecodeError {
+    /// A `%` is not followed by two hex digits.
+    InvalidEscape {
+        /// where the `%` is
+        position: usize,
+    },
+    /// The input ends less than two characters after a `%`.
+    TruncatedEscape {
+        /// where the `%` is
+        position: usize,
+    },
+    /// The decoded value is not 20 bytes long.
+    Length(BadPeerIdLengthError),
+}
+
+impl fmt::Display for UrlDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidEscape { position } => write!(
+                f,
+                "Invalid percent-encoding at byte {}, expected two hex digits after %",
+                position
+            ),
+            Self::TruncatedEscape { position } => write!(
+                f,
+                "Invalid percent-encoding at byte {}, input ends in the middle of an escape",
+                position
+            ),
+            Self::Length(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for UrlDecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Length(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Returned when an HTTP tracker announce query can't be parsed.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum AnnounceError {
+    /// A required parameter is missing. Includes the parameter name.
+    Missing(&'static str),
+    /// `info_hash` or `peer_id` doesn't decode to 20 bytes.
+    Length {
+        /// the parameter name
+        param: &'static str,
+        /// the decoded length
+        len: usize,
+    },
+    /// `info_hash` or `peer_id` is not validly percent-encoded.
+    Decode {
+        /// the parameter name
+        param: &'static str,
+        /// the underlying decoding error
+        error: UrlDecodeError,
+    },
+    /// A parameter has a value that doesn't make sense for it, such as a negative port.
+    Invalid {
+        /// the parameter name
+        param: &'static str,
+        /// the offending value, percent-decoded and lossily converted to UTF-8
+        value: String,
+    },
+}
+
+impl fmt::Display for AnnounceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Missing(param) => write!(f, "Invalid announce, missing {}", param),
+            Self::Length { param, len } => write!(
+                f,
+                "Invalid announce, expected a 20 bytes long {}, got {} bytes",
+                param, len
+            ),
+            Self::Decode { param, error } => {
+                write!(f, "Invalid announce, can't decode {}: {}", param, error)
+            }
+            Self::Invalid { param, value } => {
+                write!(f, "Invalid announce, bad {} value {:?}", param, value)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AnnounceError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Decode { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Returned when a UDP tracker (BEP 15) packet can't be decoded.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum UdpPacketError {
+    /// The packet is shorter than its type requires, or its variable-length part is cut off in
+    /// the middle of an entry.
+    Truncated {
+        /// the length the packet should have at least
+        expected: usize,
+        /// the actual length of the packet
+        actual: usize,
+    },
+    /// A connect request doesn't start with the BEP 15 magic constant. Includes the value found.
+    BadProtocolId(u64),
+    /// The action field is not one of the known actions. Includes the value found.
+    UnknownAction(u32),
+    /// The event field of an announce request is not one of the known events. Includes the
+    /// value found.
+    UnknownEvent(u32),
+}
+
+impl fmt::Display for UdpPacketError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Truncated { expected, actual } => write!(
+                f,
+                "Invalid UDP tracker packet, expected at least {} bytes, got {} bytes",
+                expected, actual
+            ),
+            Self::BadProtocolId(id) => write!(
+                f,
+                "Invalid UDP tracker connect request, bad protocol ID {:#x}",
+                id
+            ),
+            Self::UnknownAction(action) => {
+                write!(f, "Invalid UDP tracker packet, unknown action {}", action)
+            }
+            Self::UnknownEvent(event) => {
+                write!(f, "Invalid UDP tracker announce, unknown event {}", event)
+            }
+        }
+    }
+}
+
+impl std::error::Error for UdpPacketError {}
+
+/// Returned when a peer wire handshake (BEP 3) can't be decoded.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum HandshakeError {
+    /// The input ends before the end of the handshake.
+    Truncated {
+        /// the length a handshake has, 68 bytes
+        expected: usize,
+        /// the actual length of the input
+        actual: usize,
+    },
+    /// The first byte, the length of the protocol string, is not 19. Includes the value found.
+    ProtocolLength(u8),
+    /// The protocol string is not `BitTorrent protocol`. Includes the position of the first
+    /// mismatching byte.
+    ProtocolString {
+        /// position of the first byte that doesn't match, counting from the start of the handshake
+        position: usize,
+    },
+}
+
+impl fmt::Display for HandshakeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Truncated { expected, actual } => write!(
+                f,
+                "Invalid handshake, expected {} bytes, got {} bytes",
+                expected, actual
+            ),
+            Self::ProtocolLength(len) => write!(
+                f,
+                "Invalid handshake, expected a 19 bytes long protocol string, got {} bytes",
+                len
+            ),
+            Self::ProtocolString { position } => write!(
+                f,
+                "Invalid handshake, protocol string is not \"BitTorrent protocol\" at byte {}",
+                position
+            ),
+        }
+    }
+}
+
+impl std::error::Error for HandshakeError {}
+
+/// Returned when performing a handshake over a stream fails.
+#[cfg(feature = "tokio")]
+#[derive(Debug)]
+pub enum HandshakeIoError {
+    /// Reading from or writing to the stream failed.
+    Io(std::io::Error),
+    /// The remote handshake is invalid, or the stream ended in the middle of it.
+    Handshake(HandshakeError),
+    /// Sending our handshake or receiving the remote one up to the info-hash took too long.
+    Timeout,
+    /// The remote side sent its handshake up to the info-hash, but not its peer ID in time.
+    /// Includes what was received.
+    PeerIdTimeout {
+        /// the remote reserved bytes
+        reserved: [u8; 8],
+        /// the remote info-hash
+        info_hash: [u8; 20],
+    },
+}
+
+#[cfg(feature = "tokio")]
+impl fmt::Display for HandshakeIoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "Handshake failed: {}", error),
+            Self::Handshake(error) => write!(f, "{}", error),
+            Self::Timeout => write!(f, "Handshake timed out"),
+            Self::PeerIdTimeout { .. } => write!(f, "Handshake timed out waiting for the peer ID"),
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl std::error::Error for HandshakeIoError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            Self::Handshake(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl From<std::io::Error> for HandshakeIoError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl From<HandshakeError> for HandshakeIoError {
+    fn from(error: HandshakeError) -> Self {
+        Self::Handshake(error)
+    }
+}
+
+/// Returned when a latin-1 "binary string", as used by WebSocket trackers, can't be decoded into
+/// a [`PeerId`](crate::PeerId).
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Latin1Error {
+    /// A character is above U+00FF, so it doesn't stand for a byte.
+    NotLatin1 {
+        /// position of the character, counting in characters
+        position: usize,
+        /// the offending character
+        c: char,
+    },
+    /// The string is not 20 characters long.
+    Length(BadPeerIdLengthError),
+}
+
+impl fmt::Display for Latin1Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NotLatin1 { position, c } => write!(
+                f,
+                "Invalid binary string, character {:?} at {} is above U+00FF",
+                c, position
+            ),
+            Self::Length(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for Latin1Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Length(error) => Some(error),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,405 @@
+//! Generating peer IDs for your own client.
+//!
+//! Random generation with a caller-supplied RNG requires the `rand` feature, which pulls in
+//! [`rand_core`](https://docs.rs/rand_core). [`PeerId::generate_seeded`] is always available and
+//! is meant for tests.
+
+use crate::errors::BadPrefixError;
+use crate::version::{ClientVersion, VersionEncoding};
+use crate::PeerId;
+use std::fmt;
+use std::str;
+
+/// A validated Azureus-style prefix, `-XXvvvv-`, where `XX` is a two-character client code and
+/// `vvvv` are four version characters, all ASCII alphanumeric.
+///
+/// ```
+/// # use tdyne_peer_id::generate::AzureusPrefix;
+/// let prefix = AzureusPrefix::new("TR", "4050").unwrap();
+/// assert_eq!(prefix.to_string(), "-TR4050-");
+/// assert!(AzureusPrefix::new("TRX", "4050").is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AzureusPrefix {
+    code: [u8; 2],
+    version: [u8; 4],
+}
+
+impl AzureusPrefix {
+    /// Validates the client code and the raw version characters. Both are taken as is, encoding
+    /// the version is up to the caller.
+    pub fn new(code: &str, version: &str) -> Result<Self, BadPrefixError> {
+        if let Some(c) = code
+            .chars()
+            .chain(version.chars())
+            .find(|c| !c.is_ascii_alphanumeric())
+        {
+            return Err(BadPrefixError::NotAlphanumeric(c));
+        }
+        let code: [u8; 2] = code
+            .as_bytes()
+            .try_into()
+            .map_err(|_| BadPrefixError::CodeLength(code.len()))?;
+        let version: [u8; 4] = version
+            .as_bytes()
+            .try_into()
+            .map_err(|_| BadPrefixError::VersionLength(version.len()))?;
+        Ok(Self { code, version })
+    }
+
+    /// Encodes `version` with `encoding`, failing with [`BadPrefixError::UnencodableVersion`] if
+    /// it can't be represented exactly.
+    ///
+    /// ```
+    /// # use tdyne_peer_id::generate::AzureusPrefix;
+    /// # use tdyne_peer_id::version::{Channel, ClientVersion, VersionEncoding};
+    /// let version = ClientVersion::new(4, 0, 0, 0).with_channel(Channel::Beta);
+    /// let prefix = AzureusPrefix::from_version("TR", version, VersionEncoding::Transmission);
+    /// assert_eq!(prefix.unwrap().to_string(), "-TR400B-");
+    /// ```
+    pub fn from_version(
+        code: &str,
+        version: ClientVersion,
+        encoding: VersionEncoding,
+    ) -> Result<Self, BadPrefixError> {
+        let chars = version
+            .to_azureus(encoding)
+            .ok_or(BadPrefixError::UnencodableVersion(version))?;
+        Self::new(code, str::from_utf8(&chars).expect("ASCII version"))
+    }
+
+    /// Two-character client code.
+    pub fn client_code(&self) -> &str {
+        str::from_utf8(&self.code).expect("ASCII client code")
+    }
+
+    /// Four raw version characters.
+    pub fn version(&self) -> &str {
+        str::from_utf8(&self.version).expect("ASCII version")
+    }
+
+    /// The full 8-byte prefix, dashes included.
+    pub fn to_bytes(&self) -> [u8; 8] {
+        let mut bytes = [b'-'; 8];
+        bytes[1..3].copy_from_slice(&self.code);
+        bytes[3..7].copy_from_slice(&self.version);
+        bytes
+    }
+}
+
+impl fmt::Display for AzureusPrefix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "-{}{}-", self.client_code(), self.version())
+    }
+}
+
+/// Which bytes can appear in the random part of a generated peer ID.
+///
+/// Some trackers and tools handle non-printable peer IDs poorly, so clients tend to pick an
+/// alphabet and stick to it. Bytes are drawn uniformly from the alphabet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SuffixAlphabet {
+    /// Any byte, `0x00` to `0xff`.
+    #[default]
+    Binary,
+    /// ASCII letters, digits and `-_.!~*()`, same as libtorrent. These are unreserved
+    /// characters in RFC 2396, so they are left as is by most URL encoders.
+    UrlSafe,
+    /// ASCII digits only.
+    Digits,
+    /// ASCII digits and lowercase letters, same as Transmission.
+    Base36Lower,
+}
+
+impl SuffixAlphabet {
+    fn chars(self) -> Option<&'static [u8]> {
+        match self {
+            Self::Binary => None,
+            Self::UrlSafe => {
+                Some(b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ-_.!~*()")
+            }
+            Self::Digits => Some(b"0123456789"),
+            Self::Base36Lower => Some(b"0123456789abcdefghijklmnopqrstuvwxyz"),
+        }
+    }
+
+    /// Checks whether `byte` can be produced with this alphabet.
+    pub fn contains(self, byte: u8) -> bool {
+        match self.chars() {
+            Some(chars) => chars.contains(&byte),
+            None => true,
+        }
+    }
+
+    /// Fills `dst` with random characters from the alphabet. `random_bytes` is a source of
+    /// uniformly distributed bytes; rejection sampling keeps the output uniform as well.
+    pub(crate) fn fill(self, dst: &mut [u8], mut random_bytes: impl FnMut(&mut [u8])) {
+        let Some(chars) = self.chars() else {
+            random_bytes(dst);
+            return;
+        };
+        // the largest multiple of the alphabet length that fits into a byte
+        let limit = 256 - 256 % chars.len();
+        let mut buf = [0; 1];
+        for b in dst {
+            *b = loop {
+                random_bytes(&mut buf);
+                if usize::from(buf[0]) < limit {
+                    break chars[usize::from(buf[0]) % chars.len()];
+                }
+            };
+        }
+    }
+}
+
+impl PeerId {
+    /// Generates a peer ID with the given prefix, filling the remaining 12 bytes from `rng`.
+    /// Any byte can appear in the suffix, see [`PeerId::generate_with`] to change that.
+    ///
+    /// ```
+    /// # use tdyne_peer_id::PeerId;
+    /// # use tdyne_peer_id::generate::AzureusPrefix;
+    /// let prefix = AzureusPrefix::new("TR", "4050").unwrap();
+    /// let peer_id = PeerId::generate(&prefix, &mut rand::rng());
+    /// assert_eq!(&peer_id.0[..8], b"-TR4050-");
+    /// ```
+    #[cfg(feature = "rand")]
+    pub fn generate<R: rand_core::RngCore + ?Sized>(prefix: &AzureusPrefix, rng: &mut R) -> Self {
+        Self::generate_with(prefix, SuffixAlphabet::Binary, rng)
+    }
+
+    /// Generates a peer ID with the given prefix and the remaining 12 bytes drawn from
+    /// `alphabet`.
+    ///
+    /// ```
+    /// # use tdyne_peer_id::PeerId;
+    /// # use tdyne_peer_id::generate::{AzureusPrefix, SuffixAlphabet};
+    /// let prefix = AzureusPrefix::new("TR", "4050").unwrap();
+    /// let peer_id = PeerId::generate_with(&prefix, SuffixAlphabet::Digits, &mut rand::rng());
+    /// assert!(peer_id.0[8..].iter().all(u8::is_ascii_digit));
+    /// ```
+    #[cfg(feature = "rand")]
+    pub fn generate_with<R: rand_core::RngCore + ?Sized>(
+        prefix: &AzureusPrefix,
+        alphabet: SuffixAlphabet,
+        rng: &mut R,
+    ) -> Self {
+        Self::with_prefix(prefix, |suffix| {
+            alphabet.fill(suffix, |buf| rng.fill_bytes(buf))
+        })
+    }
+
+    /// Same as `generate`, but deterministic: the same prefix and seed always give the same peer
+    /// ID. Meant for tests, as the seed is easy to guess.
+    ///
+    /// ```
+    /// # use tdyne_peer_id::PeerId;
+    /// # use tdyne_peer_id::generate::AzureusPrefix;
+    /// let prefix = AzureusPrefix::new("TR", "4050").unwrap();
+    /// let peer_id = PeerId::generate_seeded(&prefix, 42);
+    /// assert_eq!(peer_id, PeerId::generate_seeded(&prefix, 42));
+    /// assert_ne!(peer_id, PeerId::generate_seeded(&prefix, 43));
+    /// ```
+    pub fn generate_seeded(prefix: &AzureusPrefix, seed: u64) -> Self {
+        Self::generate_seeded_with(prefix, SuffixAlphabet::Binary, seed)
+    }
+
+    /// Same as `generate_with`, but deterministic, see [`PeerId::generate_seeded`].
+    pub fn generate_seeded_with(
+        prefix: &AzureusPrefix,
+        alphabet: SuffixAlphabet,
+        seed: u64,
+    ) -> Self {
+        let mut rng = SplitMix64(seed);
+        Self::with_prefix(prefix, |suffix| {
+            alphabet.fill(suffix, |buf| rng.fill_bytes(buf))
+        })
+    }
+
+    /// Generates a peer ID that reveals nothing about the client: all 20 bytes are drawn from
+    /// `alphabet`, same as clients in anonymous mode do. libtorrent uses
+    /// [`SuffixAlphabet::UrlSafe`] for that.
+    ///
+    /// Random bytes can look like a known [`Style`] by accident, which would make the peer ID
+    /// stand out, so such peer IDs are discarded and generated again. That only happens for a
+    /// tiny fraction of peer IDs, so the output is still indistinguishable from uniformly random.
+    ///
+    /// [`Style`]: crate::style::Style
+    ///
+    /// ```
+    /// # use tdyne_peer_id::PeerId;
+    /// # use tdyne_peer_id::generate::SuffixAlphabet;
+    /// let peer_id = PeerId::anonymous(SuffixAlphabet::UrlSafe, &mut rand::rng());
+    /// assert_eq!(peer_id.style(), None);
+    /// assert_eq!(peer_id.client_info(), None);
+    /// ```
+    #[cfg(feature = "rand")]
+    pub fn anonymous<R: rand_core::RngCore + ?Sized>(
+        alphabet: SuffixAlphabet,
+        rng: &mut R,
+    ) -> Self {
+        Self::anonymous_from(alphabet, |buf| rng.fill_bytes(buf))
+    }
+
+    /// Same as `anonymous`, but deterministic, see [`PeerId::generate_seeded`].
+    pub fn anonymous_seeded(alphabet: SuffixAlphabet, seed: u64) -> Self {
+        let mut rng = SplitMix64(seed);
+        Self::anonymous_from(alphabet, |buf| rng.fill_bytes(buf))
+    }
+
+    fn anonymous_from(alphabet: SuffixAlphabet, mut random_bytes: impl FnMut(&mut [u8])) -> Self {
+        loop {
+            let mut peer_id = Self([0; 20]);
+            alphabet.fill(&mut peer_id.0, &mut random_bytes);
+            if peer_id.style().is_none() {
+                return peer_id;
+            }
+        }
+    }
+
+    pub(crate) fn with_prefix(prefix: &AzureusPrefix, fill_suffix: impl FnOnce(&mut [u8])) -> Self {
+        let mut bytes = [0; 20];
+        bytes[..8].copy_from_slice(&prefix.to_bytes());
+        fill_suffix(&mut bytes[8..]);
+        Self(bytes)
+    }
+}
+
+/// SplitMix64, a tiny non-cryptographic PRNG with good statistical properties. Only used where
+/// determinism matters more than unpredictability.
+pub(crate) struct SplitMix64(pub(crate) u64);
+
+impl SplitMix64 {
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    pub(crate) fn fill_bytes(&mut self, dst: &mut [u8]) {
+        for chunk in dst.chunks_mut(8) {
+            let random = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&random[..chunk.len()]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn prefix_validation() {
+        assert!(AzureusPrefix::new("qB", "46A0").is_ok());
+        assert_eq!(
+            AzureusPrefix::new("T", "4050"),
+            Err(BadPrefixError::CodeLength(1))
+        );
+        assert_eq!(
+            AzureusPrefix::new("TR", "40500"),
+            Err(BadPrefixError::VersionLength(5))
+        );
+        assert_eq!(
+            AzureusPrefix::new("T-", "4050"),
+            Err(BadPrefixError::NotAlphanumeric('-'))
+        );
+        // two bytes, but not two characters
+        assert_eq!(
+            AzureusPrefix::new("µ", "4050"),
+            Err(BadPrefixError::NotAlphanumeric('µ'))
+        );
+    }
+
+    #[test]
+    fn seeded() {
+        let prefix = AzureusPrefix::new("TR", "4050").unwrap();
+        let peer_id = PeerId::generate_seeded(&prefix, 0);
+        let azureus = peer_id.parse_azureus().unwrap();
+        assert_eq!(azureus.client_code(), "TR");
+        assert_eq!(azureus.version(), "4050");
+        // SplitMix64 reference output for seed 0, pinned so that seeded peer IDs stay stable
+        assert_eq!(
+            azureus.suffix(),
+            &[0xaf, 0xcd, 0x1d, 0x7b, 0x39, 0xa8, 0x20, 0xe2, 0xf4, 0x65, 0xb9, 0xa1]
+        );
+    }
+
+    #[test]
+    fn alphabets() {
+        let prefix = AzureusPrefix::new("TR", "4050").unwrap();
+        for alphabet in [
+            SuffixAlphabet::UrlSafe,
+            SuffixAlphabet::Digits,
+            SuffixAlphabet::Base36Lower,
+        ] {
+            for seed in 0..100 {
+                let peer_id = PeerId::generate_seeded_with(&prefix, alphabet, seed);
+                assert_eq!(&peer_id.0[..8], b"-TR4050-");
+                assert!(peer_id.0[8..].iter().all(|&b| alphabet.contains(b)));
+            }
+        }
+        assert!(SuffixAlphabet::Binary.contains(0));
+        assert!(!SuffixAlphabet::Base36Lower.contains(b'A'));
+        assert!(SuffixAlphabet::UrlSafe.contains(b'~'));
+    }
+
+    #[test]
+    fn alphabet_is_uniform() {
+        let mut counts = [0; 10];
+        let mut rng = SplitMix64(1);
+        let mut digits = [0; 10_000];
+        SuffixAlphabet::Digits.fill(&mut digits, |buf| rng.fill_bytes(buf));
+        for d in digits {
+            counts[usize::from(d - b'0')] += 1;
+        }
+        // 1000 expected per digit, a biased modulo would skew the first six digits up
+        assert!(
+            counts.iter().all(|&c| (900..1100).contains(&c)),
+            "{:?}",
+            counts
+        );
+    }
+
+    #[test]
+    fn anonymous() {
+        for alphabet in [
+            SuffixAlphabet::Binary,
+            SuffixAlphabet::UrlSafe,
+            SuffixAlphabet::Digits,
+            SuffixAlphabet::Base36Lower,
+        ] {
+            for seed in 0..1000 {
+                let peer_id = PeerId::anonymous_seeded(alphabet, seed);
+                assert_eq!(peer_id.style(), None);
+                assert!(peer_id.0.iter().all(|&b| alphabet.contains(b)));
+            }
+        }
+    }
+
+    #[test]
+    fn anonymous_rejects_styles() {
+        // a source that produces an Azureus-style peer ID first, and zeroes after that
+        let mut calls = 0;
+        let peer_id = PeerId::anonymous_from(SuffixAlphabet::Binary, |buf| {
+            calls += 1;
+            if calls == 1 {
+                buf.copy_from_slice(b"-TR4050-k8hj0wgej6ch");
+            } else {
+                buf.fill(0);
+            }
+        });
+        assert_eq!(calls, 2);
+        assert_eq!(peer_id, PeerId::from([0; 20]));
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn generate() {
+        let prefix = AzureusPrefix::new("TR", "4050").unwrap();
+        let peer_id = PeerId::generate(&prefix, &mut rand::rng());
+        assert_eq!(&peer_id.0[..8], b"-TR4050-");
+    }
+}
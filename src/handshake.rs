@@ -0,0 +1,188 @@
+//! Encoding and decoding the peer wire protocol handshake (BEP 3).
+//!
+//! A handshake is 68 bytes long: the length of the protocol string (19), the protocol string
+//! `BitTorrent protocol`, 8 reserved bytes used to advertise extensions, the info-hash and the
+//! peer ID.
+
+use crate::errors::HandshakeError;
+use crate::PeerId;
+
+/// The protocol string, prefixed with its length.
+pub const PROTOCOL: &[u8; 20] = b"\x13BitTorrent protocol";
+
+/// Length of a handshake in bytes.
+pub const HANDSHAKE_LEN: usize = 68;
+
+/// A peer wire protocol handshake.
+///
+/// ```
+/// # use tdyne_peer_id::PeerId;
+/// # use tdyne_peer_id::handshake::Handshake;
+/// let handshake = Handshake {
+///     reserved: [0, 0, 0, 0, 0, 0x10, 0, 0x05],
+///     info_hash: [0xab; 20],
+///     peer_id: PeerId::from(b"-TR4050-k8hj0wgej6ch"),
+/// };
+/// let bytes = handshake.to_bytes();
+/// assert_eq!(&bytes[..20], b"\x13BitTorrent protocol");
+/// assert_eq!(&bytes[48..], b"-TR4050-k8hj0wgej6ch");
+///
+/// let parsed = Handshake::parse(&bytes).unwrap();
+/// assert_eq!(parsed.peer_id.client_info().unwrap().name, "Transmission");
+/// assert!(parsed.supports_extension_protocol());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Handshake {
+    /// Reserved bytes, each set bit advertises support for an extension.
+    pub reserved: [u8; 8],
+    /// The torrent's info-hash.
+    pub info_hash: [u8; 20],
+    /// The sender's peer ID.
+    pub peer_id: PeerId,
+}
+
+impl Handshake {
+    /// Decodes a handshake from the start of `input`. Anything after the first 68 bytes is
+    /// ignored, so a captured connection can be passed as is.
+    ///
+    /// The protocol string is checked before the length, so a truncated input that already
+    /// diverges from a handshake fails with [`HandshakeError::ProtocolLength`] or
+    /// [`HandshakeError::ProtocolString`] rather than [`HandshakeError::Truncated`].
+    ///
+    /// ```
+    /// # use tdyne_peer_id::handshake::Handshake;
+    /// # use tdyne_peer_id::errors::HandshakeError;
+    /// assert_eq!(
+    ///     Handshake::parse(b"\x13BitTorrent protocol\0\0"),
+    ///     Err(HandshakeError::Truncated { expected: 68, actual: 22 })
+    /// );
+    /// assert_eq!(
+    ///     Handshake::parse(b"GET / HTTP/1.1\r\n"),
+    ///     Err(HandshakeError::ProtocolLength(b'G'))
+    /// );
+    /// ```
+    pub fn parse(input: &[u8]) -> Result<Self, HandshakeError> {
+        check_protocol(input)?;
+        let truncated = HandshakeError::Truncated {
+            expected: HANDSHAKE_LEN,
+            actual: input.len(),
+        };
+        let input = input.get(..HANDSHAKE_LEN).ok_or(truncated)?;
+        let mut handshake = Self {
+            reserved: [0; 8],
+            info_hash: [0; 20],
+            peer_id: PeerId([0; 20]),
+        };
+        handshake.reserved.copy_from_slice(&input[20..28]);
+        handshake.info_hash.copy_from_slice(&input[28..48]);
+        handshake.peer_id.0.copy_from_slice(&input[48..]);
+        Ok(handshake)
+    }
+
+    /// Encodes the handshake.
+    pub fn to_bytes(&self) -> [u8; HANDSHAKE_LEN] {
+        let mut bytes = [0; HANDSHAKE_LEN];
+        bytes[..20].copy_from_slice(PROTOCOL);
+        bytes[20..28].copy_from_slice(&self.reserved);
+        bytes[28..48].copy_from_slice(&self.info_hash);
+        bytes[48..].copy_from_slice(&self.peer_id.0);
+        bytes
+    }
+
+    /// Whether the extension protocol (BEP 10) bit is set.
+    pub fn supports_extension_protocol(&self) -> bool {
+        self.reserved[5] & 0x10 != 0
+    }
+
+    /// Whether the fast extension (BEP 6) bit is set.
+    pub fn supports_fast_extension(&self) -> bool {
+        self.reserved[7] & 0x04 != 0
+    }
+
+    /// Whether the DHT (BEP 5) bit is set.
+    pub fn supports_dht(&self) -> bool {
+        self.reserved[7] & 0x01 != 0
+    }
+}
+
+/// Checks as much of the protocol string as `input` contains, so that a stream that is not
+/// a BitTorrent connection can be rejected after the first byte.
+pub(crate) fn check_protocol(input: &[u8]) -> Result<(), HandshakeError> {
+    match input.first() {
+        Some(&len) if len != PROTOCOL[0] => Err(HandshakeError::ProtocolLength(len)),
+        _ => match input.iter().zip(PROTOCOL).position(|(a, b)| a != b) {
+            Some(position) => Err(HandshakeError::ProtocolString { position }),
+            None => Ok(()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn round_trip() {
+        let handshake = Handshake {
+            reserved: [0, 0, 0, 0, 0, 0x10, 0, 0x04],
+            info_hash: [7; 20],
+            peer_id: PeerId::from(b"-qB46A0-k8hj0wgej6ch"),
+        };
+        let mut bytes = handshake.to_bytes().to_vec();
+        assert_eq!(Handshake::parse(&bytes), Ok(handshake));
+        assert!(handshake.supports_extension_protocol());
+        assert!(handshake.supports_fast_extension());
+        assert!(!handshake.supports_dht());
+
+        // a bitfield message following the handshake
+        bytes.extend(b"\x00\x00\x00\x02\x05\xff");
+        assert_eq!(Handshake::parse(&bytes), Ok(handshake));
+    }
+
+    #[test]
+    fn errors() {
+        let bytes = Handshake {
+            reserved: [0; 8],
+            info_hash: [7; 20],
+            peer_id: PeerId::from(b"-qB46A0-k8hj0wgej6ch"),
+        }
+        .to_bytes();
+
+        assert_eq!(
+            Handshake::parse(&bytes[..67]),
+            Err(HandshakeError::Truncated {
+                expected: 68,
+                actual: 67
+            })
+        );
+        assert_eq!(
+            Handshake::parse(&[]),
+            Err(HandshakeError::Truncated {
+                expected: 68,
+                actual: 0
+            })
+        );
+        assert_eq!(
+            Handshake::parse(&bytes[..5]),
+            Err(HandshakeError::Truncated {
+                expected: 68,
+                actual: 5
+            })
+        );
+
+        let mut bad = bytes;
+        bad[0] = 18;
+        assert_eq!(
+            Handshake::parse(&bad),
+            Err(HandshakeError::ProtocolLength(18))
+        );
+
+        let mut bad = bytes;
+        bad[11] = b't';
+        assert_eq!(
+            Handshake::parse(&bad[..12]),
+            Err(HandshakeError::ProtocolString { position: 11 })
+        );
+    }
+}
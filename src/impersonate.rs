@@ -0,0 +1,182 @@
+//! Generating peer IDs that look like the ones real clients generate.
+//!
+//! Meant for tracker load testing and censorship research. Each [`Profile`] reproduces a
+//! client's prefix, version encoding, and the alphabet and structure of its random suffix.
+
+use crate::errors::BadPrefixError;
+use crate::generate::{AzureusPrefix, SplitMix64, SuffixAlphabet};
+use crate::version::{ClientVersion, VersionEncoding};
+use crate::PeerId;
+
+/// Client families that [`Profile`] can impersonate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Client {
+    /// `-TR` prefix, 11 base-36 characters and a check character.
+    Transmission,
+    /// `-qB` prefix, libtorrent's URL-safe suffix.
+    QBittorrent,
+    /// `-UT` prefix, 12 random bytes.
+    MicroTorrent,
+    /// `-DE` prefix, libtorrent's URL-safe suffix.
+    Deluge,
+    /// `-LT` prefix (libtorrent by Rasterbar), URL-safe suffix.
+    Libtorrent,
+}
+
+impl Client {
+    fn code(self) -> &'static str {
+        match self {
+            Self::Transmission => "TR",
+            Self::QBittorrent => "qB",
+            Self::MicroTorrent => "UT",
+            Self::Deluge => "DE",
+            Self::Libtorrent => "LT",
+        }
+    }
+
+    fn encoding(self) -> VersionEncoding {
+        match self {
+            Self::Transmission => VersionEncoding::Transmission,
+            Self::MicroTorrent => VersionEncoding::MicroTorrent,
+            Self::Deluge => VersionEncoding::Deluge,
+            Self::QBittorrent | Self::Libtorrent => VersionEncoding::Alphanumeric,
+        }
+    }
+}
+
+/// A particular version of a particular client to impersonate.
+///
+/// ```
+/// # use tdyne_peer_id::PeerId;
+/// # use tdyne_peer_id::impersonate::{Client, Profile};
+/// # use tdyne_peer_id::version::ClientVersion;
+/// let profile = Profile::new(Client::QBittorrent, ClientVersion::new(4, 6, 10, 0)).unwrap();
+/// let peer_id = PeerId::impersonate_seeded(&profile, 1);
+/// assert_eq!(&peer_id.0[..8], b"-qB46A0-");
+/// assert_eq!(peer_id.client_info().unwrap().name, "qBittorrent");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Profile {
+    client: Client,
+    prefix: AzureusPrefix,
+}
+
+impl Profile {
+    /// Fails if the client can't encode `version` in its prefix, for example a Transmission 2.x
+    /// version with a non-zero patch component.
+    pub fn new(client: Client, version: ClientVersion) -> Result<Self, BadPrefixError> {
+        let prefix = AzureusPrefix::from_version(client.code(), version, client.encoding())?;
+        Ok(Self { client, prefix })
+    }
+
+    /// The impersonated client.
+    pub fn client(&self) -> Client {
+        self.client
+    }
+
+    /// The prefix all generated peer IDs share.
+    pub fn prefix(&self) -> &AzureusPrefix {
+        &self.prefix
+    }
+
+    fn fill_suffix(&self, suffix: &mut [u8], mut random_bytes: impl FnMut(&mut [u8])) {
+        match self.client {
+            Client::Transmission => {
+                // the last character makes the sum of all the suffix digits divisible by 36,
+                // see `tr_peerIdInit` in libtransmission
+                let (random, check) = suffix.split_at_mut(suffix.len() - 1);
+                SuffixAlphabet::Base36Lower.fill(random, &mut random_bytes);
+                let sum: u32 = random
+                    .iter()
+                    .map(|&b| char::from(b).to_digit(36).expect("base-36 digit"))
+                    .sum();
+                let digit = char::from_digit((36 - sum % 36) % 36, 36).expect("base-36 digit");
+                check[0] = digit as u8;
+            }
+            Client::QBittorrent | Client::Deluge | Client::Libtorrent => {
+                SuffixAlphabet::UrlSafe.fill(suffix, random_bytes)
+            }
+            Client::MicroTorrent => SuffixAlphabet::Binary.fill(suffix, random_bytes),
+        }
+    }
+}
+
+impl PeerId {
+    /// Generates a peer ID indistinguishable from the ones generated by the profile's client,
+    /// using `rng` for the random part.
+    ///
+    /// ```
+    /// # use tdyne_peer_id::PeerId;
+    /// # use tdyne_peer_id::impersonate::{Client, Profile};
+    /// # use tdyne_peer_id::version::ClientVersion;
+    /// let profile = Profile::new(Client::Transmission, ClientVersion::new(4, 0, 5, 0)).unwrap();
+    /// let peer_id = PeerId::impersonate(&profile, &mut rand::rng());
+    /// assert_eq!(&peer_id.0[..8], b"-TR4050-");
+    /// ```
+    #[cfg(feature = "rand")]
+    pub fn impersonate<R: rand_core::RngCore + ?Sized>(profile: &Profile, rng: &mut R) -> Self {
+        Self::with_prefix(profile.prefix(), |suffix| {
+            profile.fill_suffix(suffix, |buf| rng.fill_bytes(buf))
+        })
+    }
+
+    /// Same as `impersonate`, but deterministic, see [`PeerId::generate_seeded`].
+    pub fn impersonate_seeded(profile: &Profile, seed: u64) -> Self {
+        let mut rng = SplitMix64(seed);
+        Self::with_prefix(profile.prefix(), |suffix| {
+            profile.fill_suffix(suffix, |buf| rng.fill_bytes(buf))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn transmission_check_character() {
+        let profile = Profile::new(Client::Transmission, ClientVersion::new(2, 94, 0, 0)).unwrap();
+        for seed in 0..100 {
+            let peer_id = PeerId::impersonate_seeded(&profile, seed);
+            assert_eq!(&peer_id.0[..8], b"-TR2940-");
+            let sum: u32 = peer_id.0[8..]
+                .iter()
+                .map(|&b| char::from(b).to_digit(36).unwrap())
+                .sum();
+            assert_eq!(sum % 36, 0);
+            assert!(peer_id.0[8..].iter().all(|&b| !b.is_ascii_uppercase()));
+        }
+    }
+
+    #[test]
+    fn decodes_back() {
+        let cases = [
+            (Client::Transmission, "3.5.0", b"-TR3050-"),
+            (Client::QBittorrent, "4.6.10", b"-qB46A0-"),
+            (Client::MicroTorrent, "3.5.5", b"-UT3550-"),
+            (Client::Deluge, "2.1.1", b"-DE211s-"),
+            (Client::Libtorrent, "2.0.9", b"-LT2090-"),
+        ];
+        for (client, version, prefix) in cases {
+            let version: ClientVersion = version.parse().unwrap();
+            let profile = Profile::new(client, version).unwrap();
+            let peer_id = PeerId::impersonate_seeded(&profile, 0);
+            assert_eq!(&peer_id.0[..8], prefix);
+            let info = peer_id.client_info().unwrap();
+            assert_eq!(info.code, client.code());
+            assert_eq!(info.version, version);
+        }
+    }
+
+    #[test]
+    fn unencodable() {
+        // Transmission 3.x only has two components
+        let version = ClientVersion::new(3, 5, 5, 0);
+        assert_eq!(
+            Profile::new(Client::Transmission, version),
+            Err(BadPrefixError::UnencodableVersion(version))
+        );
+    }
+}
@@ -31,16 +31,39 @@
 //!   database and parser
 
 
+pub mod announce;
+pub mod bencode;
+pub mod consistency;
 pub mod errors;
+pub mod generate;
+pub mod handshake;
+pub mod impersonate;
+#[cfg(feature = "per-torrent")]
+pub mod per_torrent;
+pub mod registry;
+pub mod render;
+#[cfg(feature = "serde")]
+pub mod serde;
+pub mod style;
+mod text;
+#[cfg(feature = "tokio")]
+pub mod tokio;
+pub mod udp;
+pub mod url;
+pub mod version;
+pub mod webtorrent;
 
 use crate::errors::BadPeerIdLengthError;
+use crate::render::SafeRenderOptions;
 use std::borrow::Cow;
-use std::fmt;
+use std::fmt::{self, Write};
+use std::ops::Deref;
+use std::str;
 
 
 /// Represents an unparsed peer ID. It's just a thin wrapper over `[u8; 20]`.
 #[repr(transparent)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct PeerId(pub [u8; 20]);
 
 impl From<[u8; 20]> for PeerId {
@@ -73,39 +96,142 @@ impl TryFrom<&[u8]> for PeerId {
 }
 
 impl fmt::Display for PeerId {
+    /// Writes [`PeerId::to_safe_str`] without allocating.
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.to_safe())
+        f.pad(&self.to_safe_str())
+    }
+}
+
+impl fmt::Debug for PeerId {
+    /// Shows the [safe string](PeerId::to_safe) and the hex. With `{:#?}`, shows an annotated
+    /// hexdump instead, with the client code, the version and the random suffix on separate
+    /// lines if the peer ID follows a known [`Style`](style::Style).
+    ///
+    /// ```
+    /// # use tdyne_peer_id::PeerId;
+    /// let peer_id = PeerId::from(b"-TR4050-k8hj0wgej6\x00\xff");
+    /// assert_eq!(
+    ///     format!("{:?}", peer_id),
+    ///     r#"PeerId("-TR4050-k8hj0wgej6??", 2d5452343035302d6b38686a307767656a3600ff)"#,
+    /// );
+    /// assert_eq!(
+    ///     format!("{:#?}", peer_id),
+    ///     r#"PeerId("-TR4050-k8hj0wgej6??", Azureus) {
+    ///     00..03  client   2d 54 52                              |-TR|
+    ///     03..08  version  34 30 35 30 2d                        |4050-|
+    ///     08..20  suffix   6b 38 68 6a 30 77 67 65 6a 36 00 ff   |k8hj0wgej6..|
+    /// }"#,
+    /// );
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let safe = self.to_safe_str();
+        if !f.alternate() {
+            return write!(f, "PeerId({:?}, {:x})", safe, self);
+        }
+
+        let style = self.style();
+        let segments = match &style {
+            Some(style) => style.segments().to_vec(),
+            None => vec![("bytes", 0..20)],
+        };
+        let name = style.as_ref().map_or("unknown style", |style| style.name());
+        let width = segments.iter().map(|(_, r)| r.len()).max().unwrap_or(0);
+
+        writeln!(f, "PeerId({:?}, {}) {{", safe, name)?;
+        for (label, range) in segments {
+            let bytes = &self.0[range.clone()];
+            write!(f, "    {:02}..{:02}  {:<8} ", range.start, range.end, label)?;
+            for b in bytes {
+                write!(f, "{:02x} ", b)?;
+            }
+            write!(f, "{:1$}  |", "", (width - bytes.len()) * 3)?;
+            for &b in bytes {
+                let c = if matches!(b, b' '..=b'~') {
+                    char::from(b)
+                } else {
+                    '.'
+                };
+                f.write_char(c)?;
+            }
+            f.write_str("|\n")?;
+        }
+        f.write_char('}')
     }
 }
 
 impl PeerId {
-    /// Renders the [`PeerId`] into a [`Cow<'_, str>`] with every character outside base64 range
+    /// Renders the [`PeerId`] into a [`Cow<'_, str>`] with every byte outside base64 range
     /// (`0-9`, `a-z`, `A-Z`, `-`, `.`) transformed into ASCII `?`. Most clients only use those
     /// characters in their peer IDs, so this representation is good enough, while being completely
-    /// safe to show in any environment without escaping.
-    ///
-    /// Returns [`Cow<'_, str>`] despite always allocating the string at the moment in anticipation
-    /// of a future optimisation.
+    /// safe to show in any environment without escaping. The result is always 20 characters long.
     ///
-    /// Reused in the [`Display`] implementation.
+    /// Borrows the peer ID if all of its bytes are in range, and only allocates otherwise. See
+    /// [`to_safe_str`](PeerId::to_safe_str) for a version that never allocates, and
+    /// [`to_safe_with`](PeerId::to_safe_with) for other character sets and escapes.
     ///
     /// [`Cow<'_, str>`]: std::borrow::Cow
-    /// [`Display`]: std::fmt::Display
     ///
     /// ```
     /// # use tdyne_peer_id::PeerId;
+    /// # use std::borrow::Cow;
     /// let peer_id = PeerId::from(b"-TR0000-*\x00\x01d7xkqq04n");
     /// assert_eq!(peer_id.to_safe(), "-TR0000-???d7xkqq04n");
+    ///
+    /// let peer_id = PeerId::from(b"-TR0000-abcd7xkqq04n");
+    /// assert!(matches!(peer_id.to_safe(), Cow::Borrowed("-TR0000-abcd7xkqq04n")));
     /// ```
     pub fn to_safe(&self) -> Cow<'_, str> {
-        // todo: don't allocate on the happy path
-        String::from_utf8_lossy(&self.0)
-            .chars()
-            .map(|c| match c {
-                'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '.' => c,
-                _ => '?',
-            })
-            .collect()
+        self.to_safe_with(SafeRenderOptions::new())
+    }
+
+    /// Renders the [`PeerId`] the same way as [`to_safe`](PeerId::to_safe), but into a
+    /// [`SafePeerIdStr`] that lives on the stack. Reused in the [`Display`] implementation.
+    ///
+    /// [`Display`]: std::fmt::Display
+    ///
+    /// ```
+    /// # use tdyne_peer_id::PeerId;
+    /// let peer_id = PeerId::from(b"-TR0000-*\x00\x01d7xkqq04n");
+    /// let safe = peer_id.to_safe_str();
+    /// assert_eq!(&*safe, "-TR0000-???d7xkqq04n");
+    /// assert!(safe.starts_with("-TR"));
+    /// ```
+    pub fn to_safe_str(&self) -> SafePeerIdStr {
+        SafePeerIdStr(self.0.map(|b| if is_safe(b) { b } else { b'?' }))
+    }
+}
+
+fn is_safe(b: u8) -> bool {
+    matches!(b, b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'.')
+}
+
+/// A peer ID rendered by [`PeerId::to_safe_str`]: 20 ASCII characters, stored inline.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SafePeerIdStr([u8; 20]);
+
+impl Deref for SafePeerIdStr {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        str::from_utf8(&self.0).expect("only ASCII")
+    }
+}
+
+impl AsRef<str> for SafePeerIdStr {
+    fn as_ref(&self) -> &str {
+        self
+    }
+}
+
+impl fmt::Display for SafePeerIdStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self)
+    }
+}
+
+impl fmt::Debug for SafePeerIdStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
     }
 }
 
@@ -113,7 +239,6 @@ impl PeerId {
 mod tests {
     use super::*;
     use pretty_assertions::assert_eq;
-    use std::str;
 
     #[test]
     fn length_error() {
@@ -136,5 +261,31 @@ mod tests {
         let safe = "-TR0072-???d7xkqq04n";
         let peer_id = PeerId::from(bytes);
         assert_eq!(&peer_id.to_safe(), safe);
+
+        // one `?` per byte, even for valid multi-byte UTF-8
+        let peer_id = PeerId::from(b"-TR0072-\xc3\xa9\xf0\x9fd7xkqq04");
+        assert_eq!(&peer_id.to_safe(), "-TR0072-????d7xkqq04");
+        assert_eq!(peer_id.to_string(), "-TR0072-????d7xkqq04");
+        assert_eq!(format!("{:>22}", peer_id), "  -TR0072-????d7xkqq04");
+    }
+
+    #[test]
+    fn debug() {
+        let peer_id = PeerId::from(b"M7-4-3--k8hj0wgej6\x00\\");
+        let expected = r#"PeerId("M7-4-3--k8hj0wgej6??", Mainline) {
+    00..01  client   4d                                    |M|
+    01..08  version  37 2d 34 2d 33 2d 2d                  |7-4-3--|
+    08..20  suffix   6b 38 68 6a 30 77 67 65 6a 36 00 5c   |k8hj0wgej6.\|
+}"#;
+        assert_eq!(format!("{:#?}", peer_id), expected);
+
+        let peer_id = PeerId::from(&[0xff; 20]);
+        let expected = format!(
+            "PeerId(\"{}\", unknown style) {{\n    00..20  bytes    {}  |{}|\n}}",
+            "?".repeat(20),
+            "ff ".repeat(20),
+            ".".repeat(20),
+        );
+        assert_eq!(format!("{:#?}", peer_id), expected);
     }
 }
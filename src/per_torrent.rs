@@ -0,0 +1,127 @@
+//! Deriving a different peer ID for every torrent without storing any per-torrent state.
+//!
+//! A client that uses the same peer ID in every swarm can be correlated across torrents by
+//! anyone watching more than one swarm. Deriving the random part from a client secret and the
+//! info-hash gives every swarm a different, but stable, peer ID.
+//!
+//! Requires the `per-torrent` feature. The derivation uses SipHash-2-4, a keyed pseudorandom
+//! function, so without the secret the peer IDs of two torrents can't be linked.
+
+use crate::generate::{AzureusPrefix, SuffixAlphabet};
+use crate::PeerId;
+use siphasher::sip128::SipHasher24;
+
+/// Separates this use of the secret from any other use the caller might have for it.
+const DOMAIN: &[u8] = b"tdyne-peer-id/per-torrent";
+
+impl PeerId {
+    /// Derives a peer ID for the torrent with the given info-hash. The prefix is kept as is, the
+    /// remaining 12 bytes are drawn from `alphabet` and depend only on `secret` and `info_hash`.
+    ///
+    /// `secret` should be generated randomly once and persisted by the client. Anyone who knows
+    /// it can link the derived peer IDs together.
+    ///
+    /// ```
+    /// # use tdyne_peer_id::PeerId;
+    /// # use tdyne_peer_id::generate::{AzureusPrefix, SuffixAlphabet};
+    /// let prefix = AzureusPrefix::new("TR", "4050").unwrap();
+    /// let secret = [7; 16];
+    /// let alphabet = SuffixAlphabet::Base36Lower;
+    ///
+    /// let first = PeerId::derive_for_torrent(&prefix, alphabet, &secret, &[1; 20]);
+    /// let second = PeerId::derive_for_torrent(&prefix, alphabet, &secret, &[2; 20]);
+    /// assert_eq!(&first.0[..8], b"-TR4050-");
+    /// assert_ne!(first, second);
+    /// assert_eq!(first, PeerId::derive_for_torrent(&prefix, alphabet, &secret, &[1; 20]));
+    /// ```
+    pub fn derive_for_torrent(
+        prefix: &AzureusPrefix,
+        alphabet: SuffixAlphabet,
+        secret: &[u8; 16],
+        info_hash: &[u8; 20],
+    ) -> Self {
+        let mut stream = KeyStream::new(secret, info_hash);
+        Self::with_prefix(prefix, |suffix| {
+            alphabet.fill(suffix, |buf| stream.fill_bytes(buf))
+        })
+    }
+}
+
+/// SipHash-2-4 in counter mode: block `n` is the 128-bit hash of the domain, the info-hash and
+/// `n`. Rejection sampling in [`SuffixAlphabet`] can ask for more than one block.
+struct KeyStream {
+    hasher: SipHasher24,
+    input: [u8; DOMAIN.len() + 20 + 4],
+    counter: u32,
+    block: [u8; 16],
+    used: usize,
+}
+
+impl KeyStream {
+    fn new(secret: &[u8; 16], info_hash: &[u8; 20]) -> Self {
+        let mut input = [0; DOMAIN.len() + 20 + 4];
+        input[..DOMAIN.len()].copy_from_slice(DOMAIN);
+        input[DOMAIN.len()..DOMAIN.len() + 20].copy_from_slice(info_hash);
+        Self {
+            hasher: SipHasher24::new_with_key(secret),
+            input,
+            counter: 0,
+            block: [0; 16],
+            used: 16,
+        }
+    }
+
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        for b in dst {
+            if self.used == self.block.len() {
+                let counter_start = self.input.len() - 4;
+                self.input[counter_start..].copy_from_slice(&self.counter.to_le_bytes());
+                self.block = self.hasher.hash(&self.input).as_bytes();
+                self.counter += 1;
+                self.used = 0;
+            }
+            *b = self.block[self.used];
+            self.used += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn depends_on_both_secret_and_info_hash() {
+        let prefix = AzureusPrefix::new("qB", "46A0").unwrap();
+        let derive = |secret: u8, info_hash: u8| {
+            PeerId::derive_for_torrent(
+                &prefix,
+                SuffixAlphabet::Binary,
+                &[secret; 16],
+                &[info_hash; 20],
+            )
+        };
+        assert_eq!(derive(1, 1), derive(1, 1));
+        assert_ne!(derive(1, 1), derive(2, 1));
+        assert_ne!(derive(1, 1), derive(1, 2));
+        assert_eq!(&derive(1, 1).0[..8], b"-qB46A0-");
+    }
+
+    #[test]
+    fn stable_across_releases() {
+        // pinned, as changing the derivation would change every derived peer ID
+        let prefix = AzureusPrefix::new("TR", "4050").unwrap();
+        let peer_id =
+            PeerId::derive_for_torrent(&prefix, SuffixAlphabet::Base36Lower, &[0; 16], &[0; 20]);
+        assert_eq!(&peer_id.0, b"-TR4050-cz5oax5vpr5d");
+    }
+
+    #[test]
+    fn key_stream_blocks_differ() {
+        let mut stream = KeyStream::new(&[0; 16], &[0; 20]);
+        let mut bytes = [0; 32];
+        stream.fill_bytes(&mut bytes);
+        assert_ne!(bytes[..16], bytes[16..]);
+    }
+}
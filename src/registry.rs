@@ -0,0 +1,257 @@
+//! A curated registry of client codes and client names.
+//!
+//! Only clients that were seen in the wild are included. Codes are case-sensitive: `LT` and `lt`
+//! are two different libtorrents.
+
+use crate::style::Style;
+use crate::version::{ClientVersion, VersionEncoding};
+use crate::PeerId;
+
+/// A client identified from a peer ID with [`PeerId::client_info`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ClientInfo {
+    /// Human-readable client name, such as `Transmission`.
+    pub name: &'static str,
+    /// Client code as it appears in the peer ID: two characters for Azureus-style peer IDs, one
+    /// for Shadow and Mainline.
+    pub code: &'static str,
+    /// Client version decoded from the peer ID.
+    pub version: ClientVersion,
+}
+
+struct Entry {
+    code: &'static str,
+    name: &'static str,
+    /// Only meaningful for Azureus-style clients.
+    encoding: VersionEncoding,
+}
+
+const fn entry(code: &'static str, name: &'static str) -> Entry {
+    entry_with(code, name, VersionEncoding::Alphanumeric)
+}
+
+const fn entry_with(code: &'static str, name: &'static str, encoding: VersionEncoding) -> Entry {
+    Entry {
+        code,
+        name,
+        encoding,
+    }
+}
+
+/// Sorted by code, byte-wise, so it can be binary searched.
+static AZUREUS: &[Entry] = &[
+    entry("7T", "aTorrent"),
+    entry("AB", "AnyEvent::BitTorrent"),
+    entry("AG", "Ares"),
+    entry("AR", "Arctic"),
+    entry("AT", "Artemis"),
+    entry("AV", "Avicora"),
+    entry("AX", "BitPump"),
+    entry("AZ", "Azureus"),
+    entry("BB", "BitBuddy"),
+    entry("BC", "BitComet"),
+    entry("BE", "Baretorrent"),
+    entry("BF", "Bitflu"),
+    entry("BG", "BTG"),
+    entry("BI", "BiglyBT"),
+    entry("BL", "BitBlinder"),
+    entry("BP", "BitTorrent Pro"),
+    entry("BR", "BitRocket"),
+    entry("BS", "BTSlave"),
+    entry_with("BT", "BitTorrent", VersionEncoding::MicroTorrent),
+    entry("BW", "BitWombat"),
+    entry("CD", "Enhanced CTorrent"),
+    entry("CT", "CTorrent"),
+    entry_with("DE", "Deluge", VersionEncoding::Deluge),
+    entry("DP", "Propagate Data Client"),
+    entry("EB", "EBit"),
+    entry("ES", "Electric Sheep"),
+    entry("FC", "FileCroc"),
+    entry("FD", "Free Download Manager"),
+    entry("FT", "FoxTorrent"),
+    entry("FW", "FrostWire"),
+    entry("FX", "Freebox BitTorrent"),
+    entry("GS", "GSTorrent"),
+    entry("HK", "Hekate"),
+    entry("HL", "Halite"),
+    entry("HM", "hMule"),
+    entry("HN", "Hydranode"),
+    entry("IL", "iLivid"),
+    entry("JS", "Justseed.it client"),
+    entry("JT", "JavaTorrent"),
+    entry("KG", "KGet"),
+    entry("KT", "KTorrent"),
+    entry("LC", "LeechCraft"),
+    entry("LH", "LH-ABC"),
+    entry("LP", "Lphant"),
+    entry("LT", "libtorrent (Rasterbar)"),
+    entry("LW", "LimeWire"),
+    entry("MK", "Meerkat"),
+    entry("MO", "MonoTorrent"),
+    entry("MP", "MooPolice"),
+    entry("MR", "Miro"),
+    entry("MT", "MoonlightTorrent"),
+    entry("NB", "Net::BitTorrent"),
+    entry("NX", "Net Transport"),
+    entry("OS", "OneSwarm"),
+    entry("OT", "OmegaTorrent"),
+    entry("PB", "Protocol::BitTorrent"),
+    entry("PD", "Pando"),
+    entry("PI", "PicoTorrent"),
+    entry("PT", "PHPTracker"),
+    entry("QD", "QQDownload"),
+    entry("QT", "Qt 4 Torrent example"),
+    entry("RT", "Retriever"),
+    entry("RZ", "RezTorrent"),
+    entry("SB", "Swiftbit"),
+    entry("SD", "Thunder"),
+    entry("SM", "SoMud"),
+    entry("SP", "BitSpirit"),
+    entry("SS", "SwarmScope"),
+    entry("ST", "SymTorrent"),
+    entry("SZ", "Shareaza"),
+    entry("TB", "Torch"),
+    entry("TE", "Terasaur Seed Bank"),
+    entry("TL", "Tribler"),
+    entry("TN", "TorrentDotNET"),
+    entry_with("TR", "Transmission", VersionEncoding::Transmission),
+    entry("TS", "Torrentstorm"),
+    entry("TT", "TuoTu"),
+    entry("UL", "uLeecher!"),
+    entry_with("UM", "µTorrent for Mac", VersionEncoding::MicroTorrent),
+    entry_with("UT", "µTorrent", VersionEncoding::MicroTorrent),
+    entry("VG", "Vagaa"),
+    entry_with("WD", "WebTorrent Desktop", VersionEncoding::WebTorrent),
+    entry("WT", "BitLet"),
+    entry_with("WW", "WebTorrent", VersionEncoding::WebTorrent),
+    entry("WY", "FireTorrent"),
+    entry("XF", "Xfplay"),
+    entry("XL", "Xunlei"),
+    entry("XS", "XSwifter"),
+    entry("XT", "XanTorrent"),
+    entry("XX", "Xtorrent"),
+    entry("ZT", "ZipTorrent"),
+    entry("lt", "libTorrent (Rakshasa)"),
+    entry("qB", "qBittorrent"),
+    entry("st", "SharkTorrent"),
+];
+
+/// Sorted by code, byte-wise, so it can be binary searched.
+static SHADOW: &[Entry] = &[
+    entry("A", "ABC"),
+    entry("O", "Osprey Permaseed"),
+    entry("Q", "BTQueue"),
+    entry("R", "Tribler"),
+    entry("S", "Shadow"),
+    entry("T", "BitTornado"),
+    entry("U", "UPnP NAT Bit Torrent"),
+];
+
+/// Sorted by code, byte-wise, so it can be binary searched.
+static MAINLINE: &[Entry] = &[entry("M", "BitTorrent Mainline"), entry("Q", "Queen Bee")];
+
+fn find(table: &'static [Entry], code: &[u8]) -> Option<&'static Entry> {
+    table
+        .binary_search_by(|e| e.code.as_bytes().cmp(code))
+        .ok()
+        .map(|i| &table[i])
+}
+
+/// Looks up a client by its peer ID. Returns `None` if the peer ID doesn't follow any known
+/// [`Style`] or if the client code isn't in the registry.
+///
+/// ```
+/// # use tdyne_peer_id::PeerId;
+/// # use tdyne_peer_id::registry;
+/// let info = registry::lookup(&PeerId::from(b"-qB4250-k8hj0wgej6ch")).expect("known client");
+/// assert_eq!(info.name, "qBittorrent");
+/// assert_eq!(info.code, "qB");
+/// assert_eq!(info.version.to_string(), "4.2.5");
+/// ```
+pub fn lookup(peer_id: &PeerId) -> Option<ClientInfo> {
+    // client codes always start at the first or the second byte
+    let (entry, version) = match peer_id.style()? {
+        Style::Azureus(azureus) => {
+            let entry = find(AZUREUS, &peer_id.0[1..3])?;
+            (entry, ClientVersion::from_azureus(&azureus, entry.encoding))
+        }
+        Style::Shadow(shadow) => (
+            find(SHADOW, &peer_id.0[..1])?,
+            ClientVersion::from_shadow(&shadow),
+        ),
+        Style::Mainline(mainline) => (
+            find(MAINLINE, &peer_id.0[..1])?,
+            ClientVersion::from_mainline(&mainline),
+        ),
+    };
+    Some(ClientInfo {
+        name: entry.name,
+        code: entry.code,
+        version,
+    })
+}
+
+/// Version encoding of an Azureus-style client, if it's in the registry.
+pub(crate) fn azureus_encoding(code: &str) -> Option<VersionEncoding> {
+    find(AZUREUS, code.as_bytes()).map(|e| e.encoding)
+}
+
+impl PeerId {
+    /// Identifies the client that generated the peer ID. Shorthand for [`registry::lookup`].
+    ///
+    /// [`registry::lookup`]: crate::registry::lookup
+    ///
+    /// ```
+    /// # use tdyne_peer_id::PeerId;
+    /// let info = PeerId::from(b"M7-4-3--k8hj0wgej6ch").client_info().expect("known client");
+    /// assert_eq!(info.name, "BitTorrent Mainline");
+    /// assert_eq!(info.version.to_string(), "7.4.3");
+    /// ```
+    pub fn client_info(&self) -> Option<ClientInfo> {
+        lookup(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn tables_are_sorted() {
+        for table in [AZUREUS, SHADOW, MAINLINE] {
+            for pair in table.windows(2) {
+                assert!(
+                    pair[0].code < pair[1].code,
+                    "{} >= {}",
+                    pair[0].code,
+                    pair[1].code
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn lookup() {
+        let info = PeerId::from(b"-TR2940-k8hj0wgej6ch").client_info().unwrap();
+        assert_eq!(info.name, "Transmission");
+        assert_eq!(info.code, "TR");
+
+        let info = PeerId::from(b"-lt0D60-k8hj0wgej6ch").client_info().unwrap();
+        assert_eq!(info.name, "libTorrent (Rakshasa)");
+
+        let info = PeerId::from(b"T03I-----k8hj0wgej6c").client_info().unwrap();
+        assert_eq!(info.name, "BitTornado");
+        assert_eq!(info.version, ClientVersion::new(0, 3, 18, 0));
+
+        let info = PeerId::from(b"Q1-10-5--k8hj0wgej6c").client_info().unwrap();
+        assert_eq!(info.name, "Queen Bee");
+        assert_eq!(info.version, ClientVersion::new(1, 10, 5, 0));
+    }
+
+    #[test]
+    fn unknown() {
+        assert_eq!(PeerId::from(b"-ZZ2940-k8hj0wgej6ch").client_info(), None);
+        assert_eq!(PeerId::from(&[0; 20]).client_info(), None);
+    }
+}
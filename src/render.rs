@@ -0,0 +1,444 @@
+//! Configurable rendering of peer IDs into safe strings, see [`SafeRenderOptions`], and
+//! rendering for specific output contexts: HTML, JSON, terminals and CSV.
+//!
+//! The context adapters are built on the lossless escaped form of [`PeerId::to_escaped`], so the
+//! original bytes can always be recovered with [`FromStr`](std::str::FromStr) after undoing the
+//! context's own escaping.
+
+use crate::text::for_each_escaped;
+use crate::PeerId;
+use std::borrow::Cow;
+use std::fmt::{self, Write};
+use std::str;
+
+/// Bytes that are rendered as is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SafeSet {
+    /// `A-Z`, `a-z`, `0-9`, `-` and `.`, as in [`PeerId::to_safe`].
+    #[default]
+    Default,
+    /// Unreserved URL characters: `A-Z`, `a-z`, `0-9`, `-`, `.`, `_` and `~`. Covers
+    /// libtorrent's random suffixes.
+    Unreserved,
+    /// Printable ASCII, from space to `~`.
+    Printable,
+    /// The listed bytes. Bytes outside ASCII are ignored, so that the result is valid UTF-8.
+    Custom(&'static [u8]),
+}
+
+impl SafeSet {
+    /// Checks if the byte is rendered as is.
+    pub fn contains(self, b: u8) -> bool {
+        match self {
+            Self::Default => crate::is_safe(b),
+            Self::Unreserved => crate::is_safe(b) || b == b'_' || b == b'~',
+            Self::Printable => matches!(b, b' '..=b'~'),
+            Self::Custom(bytes) => b.is_ascii() && bytes.contains(&b),
+        }
+    }
+}
+
+/// What the bytes outside the [`SafeSet`] become.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum EscapeStyle {
+    /// The replacement character, `?` unless changed with
+    /// [`SafeRenderOptions::replacement`]. Lossy.
+    #[default]
+    Replace,
+    /// `\xNN` with lowercase hex. A `\` in the safe set is escaped too, so the result is lossless.
+    Hex,
+    /// `%NN` with uppercase hex. A `%` in the safe set is escaped too, so the result is lossless.
+    Percent,
+    /// U+FFFD REPLACEMENT CHARACTER, `�`. Lossy.
+    Unicode,
+}
+
+/// How [`PeerId::to_safe_with`] and [`PeerId::display_with`] render a peer ID. The default
+/// options reproduce [`PeerId::to_safe`].
+///
+/// ```
+/// # use tdyne_peer_id::PeerId;
+/// # use tdyne_peer_id::render::{EscapeStyle, SafeRenderOptions, SafeSet};
+/// let peer_id = PeerId::from(b"-LT2070-k8h_0w~e\x00\xff()");
+/// assert_eq!(peer_id.to_safe(), "-LT2070-k8h?0w?e????");
+///
+/// let options = SafeRenderOptions::new()
+///     .allowed(SafeSet::Unreserved)
+///     .escape(EscapeStyle::Hex);
+/// assert_eq!(peer_id.to_safe_with(options), r"-LT2070-k8h_0w~e\x00\xff\x28\x29");
+///
+/// let options = SafeRenderOptions::new().replacement('_');
+/// assert_eq!(peer_id.display_with(options).to_string(), "-LT2070-k8h_0w_e____");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SafeRenderOptions {
+    allowed: SafeSet,
+    replacement: char,
+    escape: EscapeStyle,
+}
+
+impl Default for SafeRenderOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SafeRenderOptions {
+    /// The options [`PeerId::to_safe`] uses: [`SafeSet::Default`], replaced with `?`.
+    pub const fn new() -> Self {
+        Self {
+            allowed: SafeSet::Default,
+            replacement: '?',
+            escape: EscapeStyle::Replace,
+        }
+    }
+
+    /// Sets the bytes that are rendered as is.
+    pub const fn allowed(self, allowed: SafeSet) -> Self {
+        Self { allowed, ..self }
+    }
+
+    /// Sets the character used by [`EscapeStyle::Replace`].
+    pub const fn replacement(self, replacement: char) -> Self {
+        Self {
+            replacement,
+            ..self
+        }
+    }
+
+    /// Sets what the bytes outside the safe set become.
+    pub const fn escape(self, escape: EscapeStyle) -> Self {
+        Self { escape, ..self }
+    }
+
+    fn is_verbatim(&self, b: u8) -> bool {
+        let introducer = match self.escape {
+            EscapeStyle::Hex => Some(b'\\'),
+            EscapeStyle::Percent => Some(b'%'),
+            EscapeStyle::Replace | EscapeStyle::Unicode => None,
+        };
+        self.allowed.contains(b) && Some(b) != introducer
+    }
+
+    fn write(&self, bytes: &[u8; 20], out: &mut impl Write) -> fmt::Result {
+        for &b in bytes {
+            if self.is_verbatim(b) {
+                out.write_char(char::from(b))?;
+                continue;
+            }
+            match self.escape {
+                EscapeStyle::Replace => out.write_char(self.replacement)?,
+                EscapeStyle::Hex => write!(out, "\\x{:02x}", b)?,
+                EscapeStyle::Percent => write!(out, "%{:02X}", b)?,
+                EscapeStyle::Unicode => out.write_char(char::REPLACEMENT_CHARACTER)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Renders a peer ID with [`SafeRenderOptions`], without allocating. Returned by
+/// [`PeerId::display_with`].
+#[derive(Debug, Clone, Copy)]
+pub struct SafeDisplay<'a> {
+    peer_id: &'a PeerId,
+    options: SafeRenderOptions,
+}
+
+impl fmt::Display for SafeDisplay<'_> {
+    /// Respects width and alignment, like the [`Display`](fmt::Display) of [`PeerId`].
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut rendered = StackStr {
+            bytes: [0; 80],
+            len: 0,
+        };
+        self.options.write(&self.peer_id.0, &mut rendered)?;
+        f.pad(str::from_utf8(&rendered.bytes[..rendered.len]).expect("only whole chars"))
+    }
+}
+
+/// Fits any rendering: each of the 20 bytes becomes at most 4 bytes, `\xNN` or a replacement
+/// `char` of up to 4 bytes in UTF-8.
+struct StackStr {
+    bytes: [u8; 80],
+    len: usize,
+}
+
+impl Write for StackStr {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+        self.bytes
+            .get_mut(self.len..end)
+            .ok_or(fmt::Error)?
+            .copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+impl PeerId {
+    /// Renders the peer ID according to `options`. Borrows the peer ID if none of its bytes
+    /// need replacing or escaping.
+    pub fn to_safe_with(&self, options: SafeRenderOptions) -> Cow<'_, str> {
+        if self.0.iter().all(|&b| options.is_verbatim(b)) {
+            // the safe sets only contain ASCII
+            return Cow::Borrowed(str::from_utf8(&self.0).expect("only ASCII"));
+        }
+        let mut rendered = String::with_capacity(40);
+        options
+            .write(&self.0, &mut rendered)
+            .expect("writing to a String doesn't fail");
+        Cow::Owned(rendered)
+    }
+
+    /// Returns a [`Display`](fmt::Display) adapter that renders the peer ID according to
+    /// `options`, for use in `format!` and logging without an intermediate string.
+    pub fn display_with(&self, options: SafeRenderOptions) -> SafeDisplay<'_> {
+        SafeDisplay {
+            peer_id: self,
+            options,
+        }
+    }
+}
+
+/// Renders a peer ID as HTML text, safe in element content and in quoted attribute values.
+/// Returned by [`PeerId::display_html`].
+#[derive(Debug, Clone, Copy)]
+pub struct HtmlDisplay<'a>(&'a PeerId);
+
+impl fmt::Display for HtmlDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for_each_escaped(&self.0 .0, |c| match c {
+            '&' => f.write_str("&amp;"),
+            '<' => f.write_str("&lt;"),
+            '>' => f.write_str("&gt;"),
+            '"' => f.write_str("&quot;"),
+            '\'' => f.write_str("&#39;"),
+            c => f.write_char(c),
+        })
+    }
+}
+
+/// Renders a peer ID as a JSON string literal, quotes included. Returned by
+/// [`PeerId::display_json`].
+#[derive(Debug, Clone, Copy)]
+pub struct JsonDisplay<'a>(&'a PeerId);
+
+impl fmt::Display for JsonDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_char('"')?;
+        for_each_escaped(&self.0 .0, |c| match c {
+            '"' => f.write_str("\\\""),
+            '\\' => f.write_str("\\\\"),
+            // so that the literal can be embedded in a <script> element
+            '<' => f.write_str("\\u003c"),
+            '>' => f.write_str("\\u003e"),
+            '&' => f.write_str("\\u0026"),
+            c => f.write_char(c),
+        })?;
+        f.write_char('"')
+    }
+}
+
+/// Renders a peer ID for terminals: printable ASCII only, so no control sequences can reach
+/// the terminal. Returned by [`PeerId::display_terminal`].
+#[derive(Debug, Clone, Copy)]
+pub struct TerminalDisplay<'a>(&'a PeerId);
+
+impl fmt::Display for TerminalDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for_each_escaped(&self.0 .0, |c| f.write_char(c))
+    }
+}
+
+/// Renders a peer ID as a CSV field. Returned by [`PeerId::display_csv`].
+#[derive(Debug, Clone, Copy)]
+pub struct CsvDisplay<'a>(&'a PeerId);
+
+impl fmt::Display for CsvDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bytes = &self.0 .0;
+        // the escaped form has no line breaks, they are escaped
+        let quoted = bytes.iter().any(|&b| b == b',' || b == b'"');
+        if quoted {
+            f.write_char('"')?;
+        }
+        // a leading `'` is prefixed too, so that stripping one `'` is always right
+        if matches!(bytes[0], b'=' | b'+' | b'-' | b'@' | b'\'') {
+            f.write_char('\'')?;
+        }
+        for_each_escaped(bytes, |c| match c {
+            '"' => f.write_str("\"\""),
+            c => f.write_char(c),
+        })?;
+        if quoted {
+            f.write_char('"')?;
+        }
+        Ok(())
+    }
+}
+
+impl PeerId {
+    /// Renders the peer ID for HTML: the escaped form with `&`, `<`, `>`, `"` and `'` replaced
+    /// by character references.
+    ///
+    /// ```
+    /// # use tdyne_peer_id::PeerId;
+    /// let peer_id = PeerId::from(b"-TR4050-<b>'&\"\x00\xff\x1b[0m");
+    /// assert_eq!(
+    ///     peer_id.display_html().to_string(),
+    ///     r"-TR4050-&lt;b&gt;&#39;&amp;&quot;\x00\xff\x1b[0m",
+    /// );
+    /// ```
+    pub fn display_html(&self) -> HtmlDisplay<'_> {
+        HtmlDisplay(self)
+    }
+
+    /// Renders the peer ID as a JSON string literal of the escaped form. `<`, `>` and `&` are
+    /// escaped as well, so the literal is safe inside HTML `<script>` elements.
+    ///
+    /// ```
+    /// # use tdyne_peer_id::PeerId;
+    /// let peer_id = PeerId::from(b"-TR4050-</script>\"\x00\xff");
+    /// assert_eq!(
+    ///     peer_id.display_json().to_string(),
+    ///     r#""-TR4050-\u003c/script\u003e\"\\x00\\xff""#,
+    /// );
+    /// ```
+    pub fn display_json(&self) -> JsonDisplay<'_> {
+        JsonDisplay(self)
+    }
+
+    /// Renders the peer ID for terminals as the escaped form, which is printable ASCII only, so
+    /// bytes like ESC can't start ANSI control sequences.
+    ///
+    /// ```
+    /// # use tdyne_peer_id::PeerId;
+    /// let peer_id = PeerId::from(b"-TR4050-\x1b[2J\x07k8hj0wg");
+    /// assert_eq!(peer_id.display_terminal().to_string(), r"-TR4050-\x1b[2J\x07k8hj0wg");
+    /// ```
+    pub fn display_terminal(&self) -> TerminalDisplay<'_> {
+        TerminalDisplay(self)
+    }
+
+    /// Renders the peer ID as a CSV field (RFC 4180): the escaped form, quoted if it contains a
+    /// `,` or a `"`. Spreadsheets treat fields starting with `=`, `+`, `-` or `@` as formulas,
+    /// so those are prefixed with `'`, which spreadsheets hide. That includes every
+    /// Azureus-style peer ID. Fields starting with `'` are prefixed as well, so to parse a field
+    /// back, strip exactly one leading `'` if there is one.
+    ///
+    /// ```
+    /// # use tdyne_peer_id::PeerId;
+    /// let peer_id = PeerId::from(b"-TR4050-k8hj0wgej6ch");
+    /// assert_eq!(peer_id.display_csv().to_string(), "'-TR4050-k8hj0wgej6ch");
+    ///
+    /// let peer_id = PeerId::from(b"=1+1,\"k8hj0wgej6ch\x00\x01");
+    /// assert_eq!(peer_id.display_csv().to_string(), r#""'=1+1,""k8hj0wgej6ch\x00\x01""#);
+    /// ```
+    pub fn display_csv(&self) -> CsvDisplay<'_> {
+        CsvDisplay(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn default_matches_to_safe() {
+        let peer_id = PeerId::from(b"-TR0000-*\x00\x01d7xkqq\\%n");
+        let options = SafeRenderOptions::default();
+        assert_eq!(peer_id.to_safe_with(options), peer_id.to_safe());
+        assert_eq!(
+            peer_id.display_with(options).to_string(),
+            peer_id.to_string()
+        );
+        assert_eq!(
+            format!("{:>25}", peer_id.display_with(options)),
+            format!("{:>25}", peer_id)
+        );
+        assert_eq!(
+            format!(
+                "{:-<85}",
+                peer_id.display_with(options.escape(EscapeStyle::Hex))
+            ),
+            format!("{:-<85}", r"-TR0000-\x2a\x00\x01d7xkqq\x5c\x25n")
+        );
+    }
+
+    #[test]
+    fn escape_styles() {
+        let peer_id = PeerId::from(b"-TR0000-*\x00\x01d7xkqq\\%n");
+        let printable = SafeRenderOptions::new().allowed(SafeSet::Printable);
+        let cases = [
+            (printable, "-TR0000-*??d7xkqq\\%n"),
+            (
+                printable.escape(EscapeStyle::Hex),
+                r"-TR0000-*\x00\x01d7xkqq\x5c%n",
+            ),
+            (
+                printable.escape(EscapeStyle::Percent),
+                "-TR0000-*%00%01d7xkqq\\%25n",
+            ),
+            (
+                SafeRenderOptions::new().escape(EscapeStyle::Unicode),
+                "-TR0000-���d7xkqq��n",
+            ),
+            (
+                SafeRenderOptions::new().allowed(SafeSet::Custom(b"-TR0\xff")),
+                "-TR0000-????????????",
+            ),
+        ];
+        for (options, expected) in cases {
+            assert_eq!(peer_id.to_safe_with(options), expected);
+            assert_eq!(peer_id.display_with(options).to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn borrows() {
+        let peer_id = PeerId::from(b"-LT2070-k8h_0w~ej6ch");
+        let options = SafeRenderOptions::new().allowed(SafeSet::Unreserved);
+        assert!(matches!(peer_id.to_safe_with(options), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn contexts_are_lossless() {
+        let mut bytes = [0; 20];
+        for (i, b) in bytes.iter_mut().enumerate() {
+            *b = b"-<>&\"',=\\\x1b\x00\x7f\xff\r\n;@+ \t"[i];
+        }
+        let peer_id = PeerId::from(bytes);
+
+        let terminal = peer_id.display_terminal().to_string();
+        assert!(terminal.bytes().all(|b| matches!(b, b' '..=b'~')));
+        assert_eq!(terminal.parse(), Ok(peer_id));
+
+        let html = peer_id.display_html().to_string();
+        let unescaped = html
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&#39;", "'")
+            .replace("&amp;", "&");
+        assert_eq!(unescaped.parse(), Ok(peer_id));
+
+        let json = peer_id.display_json().to_string();
+        assert_eq!(
+            serde_json::from_str::<String>(&json).unwrap().parse(),
+            Ok(peer_id)
+        );
+
+        let csv = peer_id.display_csv().to_string();
+        assert!(csv.starts_with("\"'-"), "{}", csv);
+        assert!(!csv.contains('\n'));
+        let field = csv[2..csv.len() - 1].replace("\"\"", "\"");
+        assert_eq!(field.parse(), Ok(peer_id));
+
+        let peer_id = PeerId::from(b"'TR4050-k8hj0wgej6ch");
+        let csv = peer_id.display_csv().to_string();
+        assert_eq!(csv, "''TR4050-k8hj0wgej6ch");
+        assert_eq!(csv.strip_prefix('\'').unwrap().parse(), Ok(peer_id));
+    }
+}
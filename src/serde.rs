@@ -0,0 +1,282 @@
+//! [Serde](https://serde.rs) support, enabled with the `serde` feature.
+//!
+//! By default, [`PeerId`] is serialised as a lowercase hex string in human-readable formats like
+//! JSON, and as a tuple of exactly 20 bytes in binary formats like bincode, so there is no length
+//! prefix. Deserialising accepts hex in either case, a byte string, or a sequence of bytes, and
+//! fails with
+//! [`BadPeerIdLengthError`]'s message if there aren't exactly 20 bytes.
+//!
+//! For human-readable output that is easier to eyeball, use [`escaped`] with
+//! `#[serde(with = "tdyne_peer_id::serde::escaped")]`.
+//!
+//! ```
+//! # use tdyne_peer_id::PeerId;
+//! let peer_id = PeerId::from(b"-TR4050-k8hj0wgej6ch");
+//! let json = serde_json::to_string(&peer_id).unwrap();
+//! assert_eq!(json, r#""2d5452343035302d6b38686a307767656a366368""#);
+//! assert_eq!(serde_json::from_str::<PeerId>(&json).unwrap(), peer_id);
+//! ```
+
+use crate::errors::BadPeerIdLengthError;
+use crate::{text, PeerId};
+use ::serde::de::{self, Deserializer, SeqAccess, Visitor};
+use ::serde::ser::{SerializeTuple, Serializer};
+use ::serde::{Deserialize, Serialize};
+use std::fmt;
+
+impl Serialize for PeerId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(&format_args!("{:x}", self))
+        } else {
+            let mut tuple = serializer.serialize_tuple(self.0.len())?;
+            for b in &self.0 {
+                tuple.serialize_element(b)?;
+            }
+            tuple.end()
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for PeerId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(PeerIdVisitor(Text::Hex))
+        } else {
+            deserializer.deserialize_tuple(20, PeerIdVisitor(Text::Hex))
+        }
+    }
+}
+
+/// Serialises [`PeerId`] as an escaped string in human-readable formats: printable ASCII as is,
+/// `\` as `\\`, and everything else as `\xNN`. Binary formats are the same as without it.
+///
+/// ```
+/// # use tdyne_peer_id::PeerId;
+/// #[derive(serde::Serialize, serde::Deserialize)]
+/// struct Peer {
+///     #[serde(with = "tdyne_peer_id::serde::escaped")]
+///     peer_id: PeerId,
+/// }
+///
+/// let peer = Peer { peer_id: PeerId::from(b"-TR4050-k8hj0wgej6\x00\xff") };
+/// let json = serde_json::to_string(&peer).unwrap();
+/// assert_eq!(json, r#"{"peer_id":"-TR4050-k8hj0wgej6\\x00\\xff"}"#);
+/// ```
+pub mod escaped {
+    use super::{PeerIdVisitor, Text};
+    use crate::{text, PeerId};
+    use ::serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    /// Use with `#[serde(serialize_with = "tdyne_peer_id::serde::escaped::serialize")]`.
+    pub fn serialize<S: Serializer>(peer_id: &PeerId, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&text::escape(&peer_id.0))
+        } else {
+            peer_id.serialize(serializer)
+        }
+    }
+
+    /// Use with `#[serde(deserialize_with = "tdyne_peer_id::serde::escaped::deserialize")]`.
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<PeerId, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(PeerIdVisitor(Text::Escaped))
+        } else {
+            PeerId::deserialize(deserializer)
+        }
+    }
+}
+
+/// Serialises [`PeerId`] as a latin-1 "binary string" in human-readable formats, one character
+/// per byte, the way WebSocket trackers send peer IDs. Binary formats are the same as without
+/// it.
+///
+/// ```
+/// # use tdyne_peer_id::PeerId;
+/// #[derive(serde::Serialize, serde::Deserialize)]
+/// struct Announce {
+///     #[serde(with = "tdyne_peer_id::serde::latin1")]
+///     peer_id: PeerId,
+/// }
+///
+/// let json = r#"{"peer_id":"-WW0109-k8hj0wgej6\u0000\u00ff"}"#;
+/// let announce: Announce = serde_json::from_str(json).unwrap();
+/// assert_eq!(announce.peer_id, PeerId::from(b"-WW0109-k8hj0wgej6\x00\xff"));
+/// ```
+pub mod latin1 {
+    use super::{PeerIdVisitor, Text};
+    use crate::PeerId;
+    use ::serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    /// Use with `#[serde(serialize_with = "tdyne_peer_id::serde::latin1::serialize")]`.
+    pub fn serialize<S: Serializer>(peer_id: &PeerId, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&peer_id.to_latin1())
+        } else {
+            peer_id.serialize(serializer)
+        }
+    }
+
+    /// Use with `#[serde(deserialize_with = "tdyne_peer_id::serde::latin1::deserialize")]`.
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<PeerId, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(PeerIdVisitor(Text::Latin1))
+        } else {
+            PeerId::deserialize(deserializer)
+        }
+    }
+}
+
+/// How strings are interpreted when deserialising.
+#[derive(Clone, Copy)]
+enum Text {
+    Hex,
+    Escaped,
+    Latin1,
+}
+
+struct PeerIdVisitor(Text);
+
+impl<'de> Visitor<'de> for PeerIdVisitor {
+    type Value = PeerId;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            Text::Hex => f.write_str("20 bytes or a 40 characters long hex string"),
+            Text::Escaped => f.write_str("20 bytes or an escaped string"),
+            Text::Latin1 => f.write_str("20 bytes or a 20 characters long latin-1 string"),
+        }
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        let bytes = match self.0 {
+            Text::Hex => text::from_hex(v),
+            Text::Escaped => text::unescape(v),
+            Text::Latin1 => return PeerId::from_latin1(v).map_err(E::custom),
+        }
+        .ok_or_else(|| E::invalid_value(de::Unexpected::Str(v), &self))?;
+        self.visit_bytes(&bytes)
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        PeerId::try_from(v).map_err(E::custom)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut bytes = [0; 20];
+        let mut len = 0;
+        while let Some(b) = seq.next_element::<u8>()? {
+            if let Some(slot) = bytes.get_mut(len) {
+                *slot = b;
+            }
+            len += 1;
+        }
+        if len != bytes.len() {
+            return Err(de::Error::custom(BadPeerIdLengthError(len)));
+        }
+        Ok(PeerId(bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_test::{
+        assert_de_tokens, assert_de_tokens_error, assert_tokens, Compact, Configure, Readable,
+        Token,
+    };
+
+    #[test]
+    fn readable() {
+        let peer_id = PeerId::from(b"-TR4050-k8hj0wgej6\x00\xff");
+        assert_tokens(
+            &peer_id.readable(),
+            &[Token::Str("2d5452343035302d6b38686a307767656a3600ff")],
+        );
+        assert_de_tokens(
+            &peer_id.readable(),
+            &[Token::Str("2D5452343035302D6B38686A307767656A3600FF")],
+        );
+        assert_de_tokens_error::<Readable<PeerId>>(
+            &[Token::Str("2d54")],
+            "Invalid Peer Id length, expected a 20 bytes long slice, got 2 bytes",
+        );
+        assert_de_tokens_error::<Readable<PeerId>>(
+            &[Token::Str("-TR4050-")],
+            "invalid value: string \"-TR4050-\", expected 20 bytes or a 40 characters long hex \
+             string",
+        );
+    }
+
+    #[test]
+    fn compact() {
+        let peer_id = PeerId::from(b"-TR4050-k8hj0wgej6\x00\xff");
+        let mut tokens = vec![Token::Tuple { len: 20 }];
+        tokens.extend(peer_id.0.iter().map(|&b| Token::U8(b)));
+        tokens.push(Token::TupleEnd);
+        assert_tokens(&peer_id.compact(), &tokens);
+        assert_de_tokens(
+            &peer_id.compact(),
+            &[Token::Bytes(b"-TR4050-k8hj0wgej6\x00\xff")],
+        );
+        assert_de_tokens_error::<Compact<PeerId>>(
+            &[Token::Bytes(b"-TR4050-")],
+            "Invalid Peer Id length, expected a 20 bytes long slice, got 8 bytes",
+        );
+    }
+
+    #[test]
+    fn bincode() {
+        let peer_id = PeerId::from(b"-TR4050-k8hj0wgej6\x00\xff");
+        let encoded = bincode::serialize(&peer_id).unwrap();
+        assert_eq!(encoded, peer_id.0);
+        assert_eq!(bincode::deserialize::<PeerId>(&encoded).unwrap(), peer_id);
+    }
+
+    #[test]
+    fn json() {
+        let peer_id = PeerId::from(b"-TR4050-k8hj0wgej6\x00\xff");
+        let array = serde_json::to_string(&peer_id.0).unwrap();
+        assert_eq!(serde_json::from_str::<PeerId>(&array).unwrap(), peer_id);
+
+        let short = serde_json::from_str::<PeerId>("[1, 2, 3]").unwrap_err();
+        assert!(short.to_string().contains("got 3 bytes"), "{}", short);
+    }
+
+    #[test]
+    fn escaped() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Peer {
+            #[serde(with = "crate::serde::escaped")]
+            peer_id: PeerId,
+        }
+
+        let peer = Peer {
+            peer_id: PeerId::from(b"-TR4050-k8hj0wgej\\\x00\xff"),
+        };
+        let json = serde_json::to_string(&peer).unwrap();
+        assert_eq!(json, r#"{"peer_id":"-TR4050-k8hj0wgej\\\\\\x00\\xff"}"#);
+        assert_eq!(serde_json::from_str::<Peer>(&json).unwrap(), peer);
+    }
+
+    #[test]
+    fn latin1() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Peer {
+            #[serde(with = "crate::serde::latin1")]
+            peer_id: PeerId,
+        }
+
+        let peer = Peer {
+            peer_id: PeerId::from(b"-WW0109-k8hj0wge\x80\xe9\x00\xff"),
+        };
+        let json = serde_json::to_string(&peer).unwrap();
+        assert_eq!(json, "{\"peer_id\":\"-WW0109-k8hj0wge\u{80}é\\u0000ÿ\"}");
+        assert_eq!(serde_json::from_str::<Peer>(&json).unwrap(), peer);
+
+        let error = serde_json::from_str::<Peer>(r#"{"peer_id":"-WW0109-k8hj0wgej6c€"}"#);
+        let error = error.unwrap_err().to_string();
+        assert!(error.contains("is above U+00FF"), "{}", error);
+    }
+}
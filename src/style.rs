@@ -0,0 +1,420 @@
+//! Recognising the common peer ID conventions.
+//!
+//! Most clients follow one of a handful of layouts. This module only splits a [`PeerId`] into
+//! the parts of its layout. It doesn't map client codes to client names.
+
+use crate::PeerId;
+use std::ops::Range;
+use std::str;
+
+/// A peer ID that follows the Azureus convention: `-XXvvvv-` followed by 12 bytes, where `XX`
+/// is a two-character client code and `vvvv` are four version characters.
+///
+/// Nearly every modern client uses this layout (Transmission, qBittorrent, µTorrent, libtorrent
+/// and so on).
+///
+/// ```
+/// # use tdyne_peer_id::PeerId;
+/// let peer_id = PeerId::from(b"-TR2940-k8hj0wgej6ch");
+/// let azureus = peer_id.parse_azureus().expect("Azureus-style peer ID");
+/// assert_eq!(azureus.client_code(), "TR");
+/// assert_eq!(azureus.version(), "2940");
+/// assert_eq!(azureus.suffix(), b"k8hj0wgej6ch");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AzureusPeerId {
+    client_code: [u8; 2],
+    version: [u8; 4],
+    suffix: [u8; 12],
+}
+
+impl AzureusPeerId {
+    /// Two-character client code, such as `TR` or `qB`. Always ASCII alphanumeric.
+    pub fn client_code(&self) -> &str {
+        // checked in `parse_azureus`
+        str::from_utf8(&self.client_code).expect("ASCII client code")
+    }
+
+    /// Four raw version characters. Always ASCII alphanumeric. Clients don't agree on how these
+    /// should be interpreted, so they are returned as is.
+    pub fn version(&self) -> &str {
+        // checked in `parse_azureus`
+        str::from_utf8(&self.version).expect("ASCII version")
+    }
+
+    /// The 12 bytes after the prefix. Usually random, but not necessarily printable.
+    pub fn suffix(&self) -> &[u8; 12] {
+        &self.suffix
+    }
+}
+
+/// A peer ID that follows the Shadow convention: a client letter, up to five version characters
+/// padded with `-` to five, a 3 byte separator, commonly `---`, and 11 more bytes. For example,
+/// `T03I-----` is BitTornado 0.3.18 and `A310--001` is ABC 3.1.0.
+///
+/// Version characters are digits in a base-64 alphabet: `0-9`, then `A-Z` for 10 to 35, then
+/// `a-z` for 36 to 61, then `.` for 62.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ShadowPeerId {
+    client: u8,
+    version: [u8; 5],
+    version_len: u8,
+    suffix: [u8; 11],
+}
+
+impl ShadowPeerId {
+    /// Client letter, such as `T` for BitTornado or `S` for Shadow. Always ASCII alphanumeric.
+    pub fn client(&self) -> char {
+        char::from(self.client)
+    }
+
+    /// Decoded version parts, one to five of them, each between 0 and 62.
+    pub fn version_parts(&self) -> &[u8] {
+        &self.version[..usize::from(self.version_len)]
+    }
+
+    /// The 11 bytes after the prefix.
+    pub fn suffix(&self) -> &[u8; 11] {
+        &self.suffix
+    }
+}
+
+/// A peer ID that follows the BitTorrent Mainline convention: a client letter, then
+/// `major-minor-patch` with one to three decimal digits each, then `--` and the rest of the bytes.
+/// For example, `M7-4-3--` or `Q1-10-5--`.
+///
+/// The prefix doesn't have a fixed length, so the suffix doesn't either.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MainlinePeerId {
+    client: u8,
+    major: u16,
+    minor: u16,
+    patch: u16,
+    bytes: [u8; 20],
+    suffix_start: u8,
+}
+
+impl MainlinePeerId {
+    /// Client letter, such as `M` for Mainline or `Q` for Queen Bee. Always ASCII alphabetic.
+    pub fn client(&self) -> char {
+        char::from(self.client)
+    }
+
+    /// Major version, the first number after the client letter.
+    pub fn major(&self) -> u16 {
+        self.major
+    }
+
+    /// Minor version, the second number.
+    pub fn minor(&self) -> u16 {
+        self.minor
+    }
+
+    /// Patch version, the third number.
+    pub fn patch(&self) -> u16 {
+        self.patch
+    }
+
+    /// Everything after the `--` terminating the prefix, between 6 and 12 bytes long.
+    pub fn suffix(&self) -> &[u8] {
+        &self.bytes[usize::from(self.suffix_start)..]
+    }
+}
+
+/// All the recognised peer ID conventions, as returned by [`PeerId::style`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Style {
+    /// See [`AzureusPeerId`].
+    Azureus(AzureusPeerId),
+    /// See [`ShadowPeerId`].
+    Shadow(ShadowPeerId),
+    /// See [`MainlinePeerId`].
+    Mainline(MainlinePeerId),
+}
+
+impl Style {
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Self::Azureus(_) => "Azureus",
+            Self::Shadow(_) => "Shadow",
+            Self::Mainline(_) => "Mainline",
+        }
+    }
+
+    /// Byte ranges of the client code, the version and the random suffix, in this order.
+    /// Delimiters and padding belong to the part they follow, except for the leading `-` of
+    /// Azureus-style peer IDs, which belongs to the client code.
+    pub(crate) fn segments(&self) -> [(&'static str, Range<usize>); 3] {
+        let (client_end, suffix_len) = match self {
+            Self::Azureus(azureus) => (3, azureus.suffix().len()),
+            Self::Shadow(shadow) => (1, shadow.suffix().len()),
+            Self::Mainline(mainline) => (1, mainline.suffix().len()),
+        };
+        let suffix_start = 20 - suffix_len;
+        [
+            ("client", 0..client_end),
+            ("version", client_end..suffix_start),
+            ("suffix", suffix_start..20),
+        ]
+    }
+}
+
+impl PeerId {
+    /// Detects which convention the peer ID follows, if any. Tries Azureus, then Mainline, then
+    /// Shadow; the layouts don't overlap, so the order only matters for performance.
+    ///
+    /// ```
+    /// # use tdyne_peer_id::PeerId;
+    /// # use tdyne_peer_id::style::Style;
+    /// let peer_id = PeerId::from(b"Q1-10-5--k8hj0wgej6c");
+    /// let Some(Style::Mainline(mainline)) = peer_id.style() else { panic!("Mainline-style") };
+    /// assert_eq!((mainline.major(), mainline.minor(), mainline.patch()), (1, 10, 5));
+    /// ```
+    pub fn style(&self) -> Option<Style> {
+        if let Some(azureus) = self.parse_azureus() {
+            return Some(Style::Azureus(azureus));
+        }
+        if let Some(mainline) = self.parse_mainline() {
+            return Some(Style::Mainline(mainline));
+        }
+        self.parse_shadow().map(Style::Shadow)
+    }
+
+    /// Tries to interpret the peer ID as an Azureus-style one (`-XXvvvv-` and 12 more bytes).
+    /// Returns `None` if the prefix doesn't match.
+    ///
+    /// Client code and version characters are required to be ASCII alphanumeric.
+    ///
+    /// ```
+    /// # use tdyne_peer_id::PeerId;
+    /// assert!(PeerId::from(b"-qB4250-k8hj0wgej6ch").parse_azureus().is_some());
+    /// assert!(PeerId::from(b"M7-4-3--k8hj0wgej6ch").parse_azureus().is_none());
+    /// ```
+    pub fn parse_azureus(&self) -> Option<AzureusPeerId> {
+        let bytes = &self.0;
+        if bytes[0] != b'-' || bytes[7] != b'-' {
+            return None;
+        }
+        if !bytes[1..7].iter().all(u8::is_ascii_alphanumeric) {
+            return None;
+        }
+
+        let mut azureus = AzureusPeerId {
+            client_code: [0; 2],
+            version: [0; 4],
+            suffix: [0; 12],
+        };
+        azureus.client_code.copy_from_slice(&bytes[1..3]);
+        azureus.version.copy_from_slice(&bytes[3..7]);
+        azureus.suffix.copy_from_slice(&bytes[8..]);
+        Some(azureus)
+    }
+
+    /// Tries to interpret the peer ID as a Shadow-style one, used by BitTornado, ABC and their
+    /// derivatives. Returns `None` if the prefix doesn't match.
+    ///
+    /// ```
+    /// # use tdyne_peer_id::PeerId;
+    /// let shadow = PeerId::from(b"S58B-----k8hj0wgej6c").parse_shadow().expect("Shadow-style");
+    /// assert_eq!(shadow.client(), 'S');
+    /// assert_eq!(shadow.version_parts(), &[5, 8, 11]);
+    /// ```
+    pub fn parse_shadow(&self) -> Option<ShadowPeerId> {
+        let bytes = &self.0;
+        if !bytes[0].is_ascii_alphanumeric() {
+            return None;
+        }
+
+        // version characters run until the first dash, which starts the padding up to 5
+        // characters; the separator after them can be anything, so unless it's the usual `---`,
+        // at least `--` of padding is required to tell the prefix apart from random bytes
+        let version_len = bytes[1..6].iter().position(|&b| b == b'-').unwrap_or(5);
+        if version_len == 0 || bytes[1 + version_len..6].iter().any(|&b| b != b'-') {
+            return None;
+        }
+        if version_len > 3 && &bytes[6..9] != b"---" {
+            return None;
+        }
+
+        let mut version = [0; 5];
+        for (part, &c) in version.iter_mut().zip(&bytes[1..1 + version_len]) {
+            *part = shadow_digit(c)?;
+        }
+
+        let mut shadow = ShadowPeerId {
+            client: bytes[0],
+            version,
+            version_len: version_len as u8,
+            suffix: [0; 11],
+        };
+        shadow.suffix.copy_from_slice(&bytes[9..]);
+        Some(shadow)
+    }
+
+    /// Tries to interpret the peer ID as a Mainline-style one. Returns `None` if the prefix
+    /// doesn't match.
+    ///
+    /// Version components are separated by dashes and can be up to three digits long, so they
+    /// can't be read from fixed offsets.
+    ///
+    /// ```
+    /// # use tdyne_peer_id::PeerId;
+    /// let mainline = PeerId::from(b"M7-4-3--k8hj0wgej6ch").parse_mainline().expect("Mainline");
+    /// assert_eq!(mainline.client(), 'M');
+    /// assert_eq!((mainline.major(), mainline.minor(), mainline.patch()), (7, 4, 3));
+    /// assert_eq!(mainline.suffix(), b"k8hj0wgej6ch");
+    /// ```
+    pub fn parse_mainline(&self) -> Option<MainlinePeerId> {
+        let bytes = &self.0;
+        if !bytes[0].is_ascii_alphabetic() {
+            return None;
+        }
+
+        let mut pos = 1;
+        let mut version = [0; 3];
+        for part in &mut version {
+            let digits = bytes[pos..]
+                .iter()
+                .take_while(|b| b.is_ascii_digit())
+                .count();
+            if !(1..=3).contains(&digits) || bytes[pos + digits] != b'-' {
+                return None;
+            }
+            *part = bytes[pos..pos + digits]
+                .iter()
+                .fold(0, |acc, &b| acc * 10 + u16::from(b - b'0'));
+            pos += digits + 1;
+        }
+        // the patch version is followed by two dashes, not one
+        if bytes[pos] != b'-' {
+            return None;
+        }
+
+        let [major, minor, patch] = version;
+        Some(MainlinePeerId {
+            client: bytes[0],
+            major,
+            minor,
+            patch,
+            bytes: *bytes,
+            suffix_start: pos as u8 + 1,
+        })
+    }
+}
+
+fn shadow_digit(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'A'..=b'Z' => Some(c - b'A' + 10),
+        b'a'..=b'z' => Some(c - b'a' + 36),
+        b'.' => Some(62),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn azureus() {
+        let peer_id = PeerId::from(b"-lt0D60-\x00\x01\xffabcdefghi");
+        let azureus = peer_id.parse_azureus().unwrap();
+        assert_eq!(azureus.client_code(), "lt");
+        assert_eq!(azureus.version(), "0D60");
+        assert_eq!(azureus.suffix(), b"\x00\x01\xffabcdefghi");
+    }
+
+    #[test]
+    fn not_azureus() {
+        // missing the trailing dash
+        assert_eq!(PeerId::from(b"-TR29400k8hj0wgej6ch").parse_azureus(), None);
+        // non-alphanumeric version
+        assert_eq!(
+            PeerId::from(b"-TR294\x00-k8hj0wgej6ch").parse_azureus(),
+            None
+        );
+        // Shadow-style
+        assert_eq!(PeerId::from(b"T03I-----k8hj0wgej6c").parse_azureus(), None);
+    }
+
+    #[test]
+    fn shadow() {
+        let shadow = PeerId::from(b"T03I-----k8hj0wgej6c")
+            .parse_shadow()
+            .unwrap();
+        assert_eq!(shadow.client(), 'T');
+        assert_eq!(shadow.version_parts(), &[0, 3, 18]);
+        assert_eq!(shadow.suffix(), b"k8hj0wgej6c");
+
+        let shadow = PeerId::from(b"Aaz.90---k8hj0wgej6c")
+            .parse_shadow()
+            .unwrap();
+        assert_eq!(shadow.version_parts(), &[36, 61, 62, 9, 0]);
+
+        // ABC, with a separator other than `---`
+        let shadow = PeerId::from(b"A310--001v5Gysr4NxNK")
+            .parse_shadow()
+            .unwrap();
+        assert_eq!(shadow.client(), 'A');
+        assert_eq!(shadow.version_parts(), &[3, 1, 0]);
+        assert_eq!(shadow.suffix(), b"v5Gysr4NxNK");
+    }
+
+    #[test]
+    fn not_shadow() {
+        // Azureus-style
+        assert_eq!(PeerId::from(b"-TR2940-k8hj0wgej6ch").parse_shadow(), None);
+        // no version at all
+        assert_eq!(PeerId::from(b"T--------k8hj0wgej6c").parse_shadow(), None);
+        // less than `--` of padding and an unusual separator
+        assert_eq!(PeerId::from(b"T03IZ-00xk8hj0wgej6c").parse_shadow(), None);
+        assert_eq!(PeerId::from(b"T03I0001xk8hj0wgej6c").parse_shadow(), None);
+        // version characters after padding
+        assert_eq!(PeerId::from(b"T03-I----k8hj0wgej6c").parse_shadow(), None);
+    }
+
+    #[test]
+    fn mainline() {
+        let mainline = PeerId::from(b"Q1-10-5--k8hj0wgej6c")
+            .parse_mainline()
+            .unwrap();
+        assert_eq!(mainline.client(), 'Q');
+        assert_eq!(
+            (mainline.major(), mainline.minor(), mainline.patch()),
+            (1, 10, 5)
+        );
+        assert_eq!(mainline.suffix(), b"k8hj0wgej6c");
+
+        let mainline = PeerId::from(b"M100-200-300--k8hj0w")
+            .parse_mainline()
+            .unwrap();
+        assert_eq!(
+            (mainline.major(), mainline.minor(), mainline.patch()),
+            (100, 200, 300)
+        );
+        assert_eq!(mainline.suffix(), b"k8hj0w");
+    }
+
+    #[test]
+    fn not_mainline() {
+        // only one dash after the patch version
+        assert_eq!(PeerId::from(b"M7-4-3-xk8hj0wgej6ch").parse_mainline(), None);
+        // too many digits
+        assert_eq!(PeerId::from(b"M7-4000-3--k8hj0wgej").parse_mainline(), None);
+        // missing component
+        assert_eq!(PeerId::from(b"M7--3--k8hj0wgej6chx").parse_mainline(), None);
+    }
+
+    #[test]
+    fn style() {
+        let style = PeerId::from(b"-TR2940-k8hj0wgej6ch").style();
+        assert!(matches!(style, Some(Style::Azureus(_))));
+        let style = PeerId::from(b"T03I-----k8hj0wgej6c").style();
+        assert!(matches!(style, Some(Style::Shadow(_))));
+        let style = PeerId::from(b"M7-4-3--k8hj0wgej6ch").style();
+        assert!(matches!(style, Some(Style::Mainline(_))));
+        assert_eq!(PeerId::from(&[0; 20]).style(), None);
+    }
+}
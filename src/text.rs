@@ -0,0 +1,296 @@
+//! Lossless text representations of peer IDs: hex, base64 and the escaped form. Used by the
+//! formatting traits, [`FromStr`] and the serde support.
+
+use crate::errors::ParsePeerIdError;
+use crate::PeerId;
+use std::fmt;
+use std::str::FromStr;
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+impl fmt::LowerHex for PeerId {
+    /// Renders the peer ID as 40 lowercase hex digits.
+    ///
+    /// ```
+    /// # use tdyne_peer_id::PeerId;
+    /// let peer_id = PeerId::from(b"-TR4050-k8hj0wgej6\x00\xff");
+    /// assert_eq!(format!("{:x}", peer_id), "2d5452343035302d6b38686a307767656a3600ff");
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|b| write!(f, "{:02x}", b))
+    }
+}
+
+impl fmt::UpperHex for PeerId {
+    /// Renders the peer ID as 40 uppercase hex digits.
+    ///
+    /// ```
+    /// # use tdyne_peer_id::PeerId;
+    /// let peer_id = PeerId::from(b"-TR4050-k8hj0wgej6\x00\xff");
+    /// assert_eq!(format!("{:X}", peer_id), "2D5452343035302D6B38686A307767656A3600FF");
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|b| write!(f, "{:02X}", b))
+    }
+}
+
+impl PeerId {
+    /// Renders the peer ID as 28 characters of padded standard base64.
+    ///
+    /// ```
+    /// # use tdyne_peer_id::PeerId;
+    /// let peer_id = PeerId::from(b"-TR4050-k8hj0wgej6\x00\xff");
+    /// assert_eq!(peer_id.to_base64(), "LVRSNDA1MC1rOGhqMHdnZWo2AP8=");
+    /// ```
+    pub fn to_base64(&self) -> String {
+        to_base64(&self.0)
+    }
+
+    /// Renders the peer ID losslessly while keeping it readable: printable ASCII as is, `\` as
+    /// `\\`, and everything else as `\xNN`. Unlike [`to_safe`](PeerId::to_safe), the original
+    /// bytes can be recovered with [`FromStr`].
+    ///
+    /// ```
+    /// # use tdyne_peer_id::PeerId;
+    /// let peer_id = PeerId::from(b"-TR0000-*\x00\x01d7xkqq04n");
+    /// assert_eq!(peer_id.to_escaped(), r"-TR0000-*\x00\x01d7xkqq04n");
+    /// assert_eq!(peer_id.to_escaped().parse(), Ok(peer_id));
+    /// ```
+    pub fn to_escaped(&self) -> String {
+        escape(&self.0)
+    }
+}
+
+impl FromStr for PeerId {
+    type Err = ParsePeerIdError;
+
+    /// Parses any of the lossless forms, telling them apart by their shape:
+    ///
+    /// * 40 hex digits in either case, as produced by `{:x}` and `{:X}`,
+    /// * 27 or 28 characters of base64, standard or URL-safe, padded or not, as produced by
+    ///   [`to_base64`](PeerId::to_base64),
+    /// * anything else is taken to be the escaped form, as produced by
+    ///   [`to_escaped`](PeerId::to_escaped).
+    ///
+    /// The forms can't be confused: 20 bytes escaped are exactly 20 characters long unless there
+    /// are escapes, and escapes contain a backslash, which neither hex nor base64 do.
+    ///
+    /// ```
+    /// # use tdyne_peer_id::PeerId;
+    /// let peer_id = PeerId::from(b"-TR4050-k8hj0wgej6\x00\xff");
+    /// for s in [
+    ///     "2d5452343035302d6b38686a307767656a3600ff",
+    ///     "LVRSNDA1MC1rOGhqMHdnZWo2AP8=",
+    ///     r"-TR4050-k8hj0wgej6\x00\xff",
+    /// ] {
+    ///     assert_eq!(s.parse(), Ok(peer_id));
+    /// }
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = if s.len() == 40 && s.bytes().all(|b| b.is_ascii_hexdigit()) {
+            from_hex(s)
+        } else if (s.len() == 27 || s.len() == 28) && !s.contains('\\') {
+            from_base64(s)
+        } else {
+            unescape(s)
+        };
+        bytes
+            .and_then(|bytes| PeerId::try_from(bytes.as_slice()).ok())
+            .ok_or_else(|| ParsePeerIdError(s.to_owned()))
+    }
+}
+
+/// Parses hex in either case. Returns `None` if the string has an odd length or contains
+/// anything but hex digits.
+pub(crate) fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 == 1 {
+        return None;
+    }
+    hex.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            let high = char::from(pair[0]).to_digit(16)?;
+            let low = char::from(pair[1]).to_digit(16)?;
+            Some((high << 4 | low) as u8)
+        })
+        .collect()
+}
+
+/// Renders bytes as padded standard base64.
+pub(crate) fn to_base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let mut group = [0; 3];
+        group[..chunk.len()].copy_from_slice(chunk);
+        let n = u32::from(group[0]) << 16 | u32::from(group[1]) << 8 | u32::from(group[2]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(char::from(BASE64[(n >> (18 - 6 * i) & 0x3f) as usize]));
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Parses standard or URL-safe base64, with or without padding. Returns `None` on any other
+/// character, misplaced padding or an impossible length.
+pub(crate) fn from_base64(encoded: &str) -> Option<Vec<u8>> {
+    let unpadded = encoded.trim_end_matches('=');
+    if encoded.len() - unpadded.len() > 2 || unpadded.len() % 4 == 1 {
+        return None;
+    }
+    let mut bytes = Vec::with_capacity(unpadded.len() * 3 / 4);
+    for chunk in unpadded.as_bytes().chunks(4) {
+        let mut n = 0;
+        for (i, &c) in chunk.iter().enumerate() {
+            let value = match c {
+                b'A'..=b'Z' => c - b'A',
+                b'a'..=b'z' => c - b'a' + 26,
+                b'0'..=b'9' => c - b'0' + 52,
+                b'+' | b'-' => 62,
+                b'/' | b'_' => 63,
+                _ => return None,
+            };
+            n |= u32::from(value) << (18 - 6 * i);
+        }
+        bytes.extend_from_slice(&n.to_be_bytes()[1..chunk.len()]);
+    }
+    Some(bytes)
+}
+
+/// Renders printable ASCII as is, except for the backslash, which becomes `\\`. Everything else
+/// becomes `\xNN` with lowercase hex.
+pub(crate) fn escape(bytes: &[u8; 20]) -> String {
+    let mut escaped = String::with_capacity(20);
+    for_each_escaped(bytes, |c| {
+        escaped.push(c);
+        Ok(())
+    })
+    .expect("pushing to a String doesn't fail");
+    escaped
+}
+
+/// Calls `f` with each character of [`escape`]'s output, so that it can be further escaped
+/// without allocating. Stops at the first error.
+pub(crate) fn for_each_escaped(
+    bytes: &[u8; 20],
+    mut f: impl FnMut(char) -> fmt::Result,
+) -> fmt::Result {
+    let hex = |n: u8| char::from_digit(u32::from(n), 16).expect("hex digit");
+    for &b in bytes {
+        match b {
+            b'\\' => {
+                f('\\')?;
+                f('\\')?;
+            }
+            b' '..=b'~' => f(char::from(b))?,
+            _ => {
+                f('\\')?;
+                f('x')?;
+                f(hex(b >> 4))?;
+                f(hex(b & 0xf))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Parses the output of [`escape`]. Hex digits can be in either case. Returns `None` if the
+/// string contains a malformed escape or characters outside printable ASCII.
+pub(crate) fn unescape(escaped: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(20);
+    let mut rest = escaped.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        rest = tail;
+        match b {
+            b'\\' => match rest {
+                [b'\\', tail @ ..] => {
+                    bytes.push(b'\\');
+                    rest = tail;
+                }
+                [b'x', high, low, tail @ ..] => {
+                    let high = char::from(*high).to_digit(16)?;
+                    let low = char::from(*low).to_digit(16)?;
+                    bytes.push((high << 4 | low) as u8);
+                    rest = tail;
+                }
+                _ => return None,
+            },
+            b' '..=b'~' => bytes.push(b),
+            _ => return None,
+        }
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn hex_round_trip() {
+        let bytes = *b"-TR4050-\x00\x01\xff\\k8hj0wge";
+        let hex = format!("{:x}", PeerId::from(bytes));
+        assert_eq!(hex, "2d5452343035302d0001ff5c6b38686a30776765");
+        assert_eq!(from_hex(&hex).unwrap(), bytes);
+        assert_eq!(from_hex("2D54").unwrap(), b"-T");
+        assert_eq!(from_hex("2d5"), None);
+        assert_eq!(from_hex("zz"), None);
+    }
+
+    #[test]
+    fn escape_round_trip() {
+        let bytes = *b"-TR4050-\x00\x01\xff\\k8hj0wge";
+        assert_eq!(escape(&bytes), "-TR4050-\\x00\\x01\\xff\\\\k8hj0wge");
+        assert_eq!(unescape(&escape(&bytes)).unwrap(), bytes);
+        assert_eq!(unescape("\\xFF").unwrap(), b"\xff");
+        assert_eq!(unescape("\\x0"), None);
+        assert_eq!(unescape("\\n"), None);
+        assert_eq!(unescape("é"), None);
+    }
+
+    #[test]
+    fn base64_round_trip() {
+        for len in 0..=20 {
+            let bytes: Vec<u8> = (0..len).map(|i| (i * 37 + 250) as u8).collect();
+            let encoded = to_base64(&bytes);
+            assert_eq!(encoded.len() % 4, 0);
+            assert_eq!(from_base64(&encoded).unwrap(), bytes);
+            assert_eq!(from_base64(encoded.trim_end_matches('=')).unwrap(), bytes);
+        }
+        assert_eq!(to_base64(b"\xfb\xff"), "+/8=");
+        assert_eq!(from_base64("-_8").unwrap(), b"\xfb\xff");
+        assert_eq!(from_base64("A==="), None);
+        assert_eq!(from_base64("AB.="), None);
+    }
+
+    #[test]
+    fn from_str() {
+        let peer_id = PeerId::from(b"-TR4050-k8hj0wgej\\\x00\xff");
+        for s in [
+            format!("{:x}", peer_id),
+            format!("{:X}", peer_id),
+            peer_id.to_base64(),
+            peer_id.to_base64().trim_end_matches('=').to_owned(),
+            peer_id.to_escaped(),
+        ] {
+            assert_eq!(s.parse(), Ok(peer_id), "{}", s);
+        }
+
+        // printable peer IDs don't need escapes
+        let printable = PeerId::from(b"-TR4050-k8hj0wgej6ch");
+        assert_eq!("-TR4050-k8hj0wgej6ch".parse(), Ok(printable));
+
+        for bad in [
+            "",
+            "-TR4050-",
+            "-TR4050-k8hj0wgej6ch!",
+            "LVRSNDA1MC1rOGhqMHdnZWo2A.8=",
+        ] {
+            assert_eq!(bad.parse::<PeerId>(), Err(ParsePeerIdError(bad.to_owned())));
+        }
+    }
+}
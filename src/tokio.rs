@@ -0,0 +1,283 @@
+//! Performing the peer wire handshake over tokio streams, without the rest of a client.
+//!
+//! Available with the `tokio` feature.
+//!
+//! Some clients send their handshake up to the info-hash, and hold the last 20 bytes, the peer ID,
+//! until they have seen the other side's handshake. Tracker NAT checks even hang up without
+//! sending one. That's why receiving the peer ID has its own timeout, and why
+//! [`HandshakeIoError::PeerIdTimeout`] reports the part of the handshake that did arrive.
+
+use crate::errors::{HandshakeError, HandshakeIoError};
+use crate::handshake::{check_protocol, Handshake, HANDSHAKE_LEN};
+use crate::PeerId;
+use ::tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use ::tokio::time::timeout;
+use std::time::Duration;
+
+/// Length of a handshake without the peer ID.
+const HEADER_LEN: usize = HANDSHAKE_LEN - 20;
+
+/// How long each stage of a handshake may take.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Timeouts {
+    /// For sending our handshake and receiving the remote one up to and including the info-hash.
+    pub handshake: Duration,
+    /// For receiving the remote peer ID once the rest of the remote handshake arrived.
+    pub peer_id: Duration,
+}
+
+impl Default for Timeouts {
+    /// 10 seconds for each stage.
+    fn default() -> Self {
+        Self {
+            handshake: Duration::from_secs(10),
+            peer_id: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Performs the handshake as the side that opened the connection: sends `ours`, then receives
+/// the remote handshake.
+///
+/// The remote info-hash isn't compared with ours, check it if it matters.
+///
+/// ```
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// # use tdyne_peer_id::PeerId;
+/// # use tdyne_peer_id::handshake::Handshake;
+/// # use tdyne_peer_id::tokio::{initiate, respond, Timeouts};
+/// let (mut client, mut server) = tokio::io::duplex(128);
+/// let ours = Handshake {
+///     reserved: [0; 8],
+///     info_hash: [0xab; 20],
+///     peer_id: PeerId::from(b"-TR4050-k8hj0wgej6ch"),
+/// };
+/// let theirs = PeerId::from(b"-qB46A0-k8hj0wgej6ch");
+/// let (remote, _) = tokio::try_join!(
+///     initiate(&mut client, &ours, Timeouts::default()),
+///     respond(&mut server, [0; 8], theirs, Timeouts::default()),
+/// )
+/// .unwrap();
+/// assert_eq!(remote.peer_id.client_info().unwrap().name, "qBittorrent");
+/// assert_eq!(remote.info_hash, [0xab; 20]);
+/// # }
+/// ```
+pub async fn initiate<S>(
+    stream: &mut S,
+    ours: &Handshake,
+    timeouts: Timeouts,
+) -> Result<Handshake, HandshakeIoError>
+where
+    S: AsyncRead + AsyncWrite + Unpin + ?Sized,
+{
+    let mut buf = [0; HANDSHAKE_LEN];
+    timeout(timeouts.handshake, async {
+        stream.write_all(&ours.to_bytes()).await?;
+        stream.flush().await?;
+        read_header(stream, &mut buf).await
+    })
+    .await
+    .map_err(|_| HandshakeIoError::Timeout)??;
+    read_peer_id(stream, &mut buf, timeouts.peer_id).await
+}
+
+/// Performs the handshake as the side that accepted the connection: receives the remote
+/// handshake up to the info-hash, sends ours with the same info-hash, then receives the remote
+/// peer ID. Sending ours before waiting for the peer ID lets clients that hold their peer ID
+/// back complete the handshake.
+pub async fn respond<S>(
+    stream: &mut S,
+    reserved: [u8; 8],
+    peer_id: PeerId,
+    timeouts: Timeouts,
+) -> Result<Handshake, HandshakeIoError>
+where
+    S: AsyncRead + AsyncWrite + Unpin + ?Sized,
+{
+    let mut buf = [0; HANDSHAKE_LEN];
+    timeout(timeouts.handshake, async {
+        read_header(stream, &mut buf).await?;
+        let ours = Handshake {
+            reserved,
+            info_hash: buf[28..HEADER_LEN].try_into().expect("20 bytes"),
+            peer_id,
+        };
+        stream.write_all(&ours.to_bytes()).await?;
+        stream.flush().await?;
+        Ok::<_, HandshakeIoError>(())
+    })
+    .await
+    .map_err(|_| HandshakeIoError::Timeout)??;
+    read_peer_id(stream, &mut buf, timeouts.peer_id).await
+}
+
+/// Receives a handshake without sending anything, for example to inspect a captured or proxied
+/// connection.
+pub async fn read_handshake<R>(
+    reader: &mut R,
+    timeouts: Timeouts,
+) -> Result<Handshake, HandshakeIoError>
+where
+    R: AsyncRead + Unpin + ?Sized,
+{
+    let mut buf = [0; HANDSHAKE_LEN];
+    timeout(timeouts.handshake, read_header(reader, &mut buf))
+        .await
+        .map_err(|_| HandshakeIoError::Timeout)??;
+    read_peer_id(reader, &mut buf, timeouts.peer_id).await
+}
+
+/// Reads the first 48 bytes into `buf`, checking the protocol string as soon as it arrives.
+async fn read_header<R>(
+    reader: &mut R,
+    buf: &mut [u8; HANDSHAKE_LEN],
+) -> Result<(), HandshakeIoError>
+where
+    R: AsyncRead + Unpin + ?Sized,
+{
+    let mut filled = 0;
+    while filled < HEADER_LEN {
+        let n = reader.read(&mut buf[filled..HEADER_LEN]).await?;
+        if n == 0 {
+            return Err(truncated(filled));
+        }
+        filled += n;
+        check_protocol(&buf[..filled])?;
+    }
+    Ok(())
+}
+
+async fn read_peer_id<R>(
+    reader: &mut R,
+    buf: &mut [u8; HANDSHAKE_LEN],
+    peer_id_timeout: Duration,
+) -> Result<Handshake, HandshakeIoError>
+where
+    R: AsyncRead + Unpin + ?Sized,
+{
+    let read = async {
+        let mut filled = HEADER_LEN;
+        while filled < HANDSHAKE_LEN {
+            let n = reader.read(&mut buf[filled..]).await?;
+            if n == 0 {
+                return Err(truncated(filled));
+            }
+            filled += n;
+        }
+        Ok(())
+    };
+    match timeout(peer_id_timeout, read).await {
+        Ok(result) => result?,
+        Err(_) => {
+            return Err(HandshakeIoError::PeerIdTimeout {
+                reserved: buf[20..28].try_into().expect("8 bytes"),
+                info_hash: buf[28..HEADER_LEN].try_into().expect("20 bytes"),
+            })
+        }
+    }
+    Ok(Handshake::parse(buf)?)
+}
+
+fn truncated(actual: usize) -> HandshakeIoError {
+    HandshakeIoError::Handshake(HandshakeError::Truncated {
+        expected: HANDSHAKE_LEN,
+        actual,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::tokio::io::duplex;
+    use ::tokio::time::sleep;
+    use pretty_assertions::assert_eq;
+
+    fn handshake(peer_id: &[u8; 20]) -> Handshake {
+        Handshake {
+            reserved: [0, 0, 0, 0, 0, 0x10, 0, 0x05],
+            info_hash: [7; 20],
+            peer_id: PeerId::from(peer_id),
+        }
+    }
+
+    #[::tokio::test(start_paused = true)]
+    async fn late_peer_id() {
+        let (mut client, mut server) = duplex(128);
+        let theirs = handshake(b"-qB46A0-k8hj0wgej6ch");
+        let remote = async {
+            let bytes = theirs.to_bytes();
+            server.write_all(&bytes[..HEADER_LEN]).await.unwrap();
+            sleep(Duration::from_secs(5)).await;
+            server.write_all(&bytes[HEADER_LEN..]).await.unwrap();
+        };
+        let ours = handshake(b"-TR4050-k8hj0wgej6ch");
+        let (result, ()) =
+            ::tokio::join!(initiate(&mut client, &ours, Timeouts::default()), remote);
+        assert_eq!(result.unwrap(), theirs);
+    }
+
+    #[::tokio::test(start_paused = true)]
+    async fn peer_id_timeout() {
+        let (mut client, mut server) = duplex(128);
+        let theirs = handshake(b"-qB46A0-k8hj0wgej6ch");
+        server
+            .write_all(&theirs.to_bytes()[..HEADER_LEN + 5])
+            .await
+            .unwrap();
+        let timeouts = Timeouts {
+            handshake: Duration::from_secs(10),
+            peer_id: Duration::from_secs(1),
+        };
+        let error = read_handshake(&mut client, timeouts).await.unwrap_err();
+        assert!(matches!(
+            error,
+            HandshakeIoError::PeerIdTimeout {
+                reserved,
+                info_hash: [7, ..],
+            } if reserved == theirs.reserved
+        ));
+    }
+
+    #[::tokio::test(start_paused = true)]
+    async fn header_timeout() {
+        let (mut client, _server) = duplex(128);
+        let ours = handshake(b"-TR4050-k8hj0wgej6ch");
+        let error = initiate(&mut client, &ours, Timeouts::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(error, HandshakeIoError::Timeout));
+    }
+
+    #[::tokio::test]
+    async fn bad_protocol() {
+        let (mut client, mut server) = duplex(128);
+        // the connection stays open, the first bytes are enough to fail
+        server.write_all(b"\x13BitTorrent Protocol").await.unwrap();
+        let error = read_handshake(&mut client, Timeouts::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            HandshakeIoError::Handshake(HandshakeError::ProtocolString { position: 12 })
+        ));
+    }
+
+    #[::tokio::test]
+    async fn truncated() {
+        let (mut client, mut server) = duplex(128);
+        let theirs = handshake(b"-qB46A0-k8hj0wgej6ch");
+        server.write_all(&theirs.to_bytes()[..60]).await.unwrap();
+        drop(server);
+        let error = read_handshake(&mut client, Timeouts::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            HandshakeIoError::Handshake(HandshakeError::Truncated {
+                expected: 68,
+                actual: 60
+            })
+        ));
+    }
+}
@@ -0,0 +1,612 @@
+//! Encoding and decoding UDP tracker protocol (BEP 15) packets.
+//!
+//! All integers are big-endian. The peer ID sits at a fixed offset (36) of an announce request,
+//! [`Request::parse`] takes care of that and returns a [`PeerId`].
+
+use crate::announce::Event;
+use crate::errors::UdpPacketError;
+use crate::PeerId;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+
+/// The magic constant every connect request starts with.
+pub const PROTOCOL_ID: u64 = 0x0417_2710_1980;
+
+const ACTION_CONNECT: u32 = 0;
+const ACTION_ANNOUNCE: u32 = 1;
+const ACTION_SCRAPE: u32 = 2;
+const ACTION_ERROR: u32 = 3;
+
+/// A packet sent from a client to a tracker.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Request {
+    /// Asks for a connection ID. Contains the transaction ID.
+    Connect {
+        /// random value chosen by the client and echoed by the tracker
+        transaction_id: u32,
+    },
+    /// See [`AnnounceRequest`].
+    Announce(AnnounceRequest),
+    /// Asks for statistics of up to about 70 torrents.
+    Scrape {
+        /// connection ID received in a connect response
+        connection_id: u64,
+        /// random value chosen by the client and echoed by the tracker
+        transaction_id: u32,
+        /// info-hashes of the torrents
+        info_hashes: Vec<[u8; 20]>,
+    },
+}
+
+/// An announce request. The UDP counterpart of the HTTP [`AnnounceRequest`].
+///
+/// [`AnnounceRequest`]: crate::announce::AnnounceRequest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AnnounceRequest {
+    /// Connection ID received in a connect response.
+    pub connection_id: u64,
+    /// Random value chosen by the client and echoed by the tracker.
+    pub transaction_id: u32,
+    /// The torrent's info-hash.
+    pub info_hash: [u8; 20],
+    /// The client's peer ID.
+    pub peer_id: PeerId,
+    /// Bytes downloaded since the `started` event.
+    pub downloaded: u64,
+    /// Bytes left to download.
+    pub left: u64,
+    /// Bytes uploaded since the `started` event.
+    pub uploaded: u64,
+    /// `None` for regular announces. `paused` is encoded as `4`, as BEP 21 specifies.
+    pub event: Option<Event>,
+    /// IPv4 address the tracker should use instead of the sender's, `0` for the sender's.
+    pub ip: u32,
+    /// Opaque value identifying the client across IP changes.
+    pub key: u32,
+    /// How many peers the client wants, `-1` for the tracker's default.
+    pub num_want: i32,
+    /// Port the client listens on.
+    pub port: u16,
+}
+
+/// A packet sent from a tracker to a client.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Response {
+    /// Provides a connection ID.
+    Connect {
+        /// echoed from the request
+        transaction_id: u32,
+        /// to be used in the following requests
+        connection_id: u64,
+    },
+    /// Provides peers.
+    Announce {
+        /// echoed from the request
+        transaction_id: u32,
+        /// seconds to wait before the next announce
+        interval: u32,
+        /// number of peers still downloading
+        leechers: u32,
+        /// number of peers that have the whole torrent
+        seeders: u32,
+        /// peers, either all IPv4 or all IPv6, see [`AddressFamily`]
+        peers: Vec<SocketAddr>,
+    },
+    /// Provides torrent statistics, in the same order as the requested info-hashes.
+    Scrape {
+        /// echoed from the request
+        transaction_id: u32,
+        /// statistics for each torrent
+        torrents: Vec<ScrapeStats>,
+    },
+    /// The request failed.
+    Error {
+        /// echoed from the request
+        transaction_id: u32,
+        /// human-readable explanation, lossily converted to UTF-8
+        message: String,
+    },
+}
+
+/// Statistics of a single torrent in a scrape response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ScrapeStats {
+    /// Number of peers that have the whole torrent.
+    pub seeders: u32,
+    /// Number of times the torrent was downloaded.
+    pub completed: u32,
+    /// Number of peers still downloading.
+    pub leechers: u32,
+}
+
+/// Peers in announce responses are 6 bytes long for trackers reached over IPv4 and 18 bytes
+/// long for trackers reached over IPv6. The packet itself doesn't say which one it is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AddressFamily {
+    /// 4 bytes of address, 2 bytes of port.
+    V4,
+    /// 16 bytes of address, 2 bytes of port.
+    V6,
+}
+
+impl Request {
+    /// Decodes a request. Bytes after a connect or an announce request are ignored, as BEP 41
+    /// uses them for extensions.
+    ///
+    /// ```
+    /// # use tdyne_peer_id::PeerId;
+    /// # use tdyne_peer_id::udp::{AnnounceRequest, Request};
+    /// let request = AnnounceRequest {
+    ///     connection_id: 0x1234,
+    ///     transaction_id: 42,
+    ///     info_hash: [0xab; 20],
+    ///     peer_id: PeerId::from(b"-TR4050-k8hj0wgej6ch"),
+    ///     downloaded: 0,
+    ///     left: 1024,
+    ///     uploaded: 0,
+    ///     event: None,
+    ///     ip: 0,
+    ///     key: 7,
+    ///     num_want: -1,
+    ///     port: 51413,
+    /// };
+    /// let bytes = Request::Announce(request).to_bytes();
+    /// assert_eq!(bytes.len(), 98);
+    /// assert_eq!(&bytes[36..56], b"-TR4050-k8hj0wgej6ch");
+    ///
+    /// let Ok(Request::Announce(parsed)) = Request::parse(&bytes) else { panic!() };
+    /// assert_eq!(parsed.peer_id, request.peer_id);
+    /// ```
+    pub fn parse(packet: &[u8]) -> Result<Self, UdpPacketError> {
+        let mut r = Reader::new(packet, 16)?;
+        let connection_id = r.u64();
+        let action = r.u32();
+        let transaction_id = r.u32();
+
+        match action {
+            ACTION_CONNECT => {
+                if connection_id != PROTOCOL_ID {
+                    return Err(UdpPacketError::BadProtocolId(connection_id));
+                }
+                Ok(Self::Connect { transaction_id })
+            }
+            ACTION_ANNOUNCE => {
+                r.require(98)?;
+                let info_hash = r.array();
+                let peer_id = PeerId(r.array());
+                let downloaded = r.u64();
+                let left = r.u64();
+                let uploaded = r.u64();
+                let event = match r.u32() {
+                    0 => None,
+                    1 => Some(Event::Completed),
+                    2 => Some(Event::Started),
+                    3 => Some(Event::Stopped),
+                    4 => Some(Event::Paused),
+                    event => return Err(UdpPacketError::UnknownEvent(event)),
+                };
+                Ok(Self::Announce(AnnounceRequest {
+                    connection_id,
+                    transaction_id,
+                    info_hash,
+                    peer_id,
+                    downloaded,
+                    left,
+                    uploaded,
+                    event,
+                    ip: r.u32(),
+                    key: r.u32(),
+                    num_want: r.u32() as i32,
+                    port: r.u16(),
+                }))
+            }
+            ACTION_SCRAPE => {
+                let info_hashes = r.entries(20, |r| r.array())?;
+                Ok(Self::Scrape {
+                    connection_id,
+                    transaction_id,
+                    info_hashes,
+                })
+            }
+            action => Err(UdpPacketError::UnknownAction(action)),
+        }
+    }
+
+    /// Encodes the request.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = Vec::with_capacity(98);
+        match self {
+            Self::Connect { transaction_id } => {
+                w.extend(PROTOCOL_ID.to_be_bytes());
+                w.extend(ACTION_CONNECT.to_be_bytes());
+                w.extend(transaction_id.to_be_bytes());
+            }
+            Self::Announce(announce) => {
+                w.extend(announce.connection_id.to_be_bytes());
+                w.extend(ACTION_ANNOUNCE.to_be_bytes());
+                w.extend(announce.transaction_id.to_be_bytes());
+                w.extend(announce.info_hash);
+                w.extend(announce.peer_id.0);
+                w.extend(announce.downloaded.to_be_bytes());
+                w.extend(announce.left.to_be_bytes());
+                w.extend(announce.uploaded.to_be_bytes());
+                let event: u32 = match announce.event {
+                    None => 0,
+                    Some(Event::Completed) => 1,
+                    Some(Event::Started) => 2,
+                    Some(Event::Stopped) => 3,
+                    Some(Event::Paused) => 4,
+                };
+                w.extend(event.to_be_bytes());
+                w.extend(announce.ip.to_be_bytes());
+                w.extend(announce.key.to_be_bytes());
+                w.extend(announce.num_want.to_be_bytes());
+                w.extend(announce.port.to_be_bytes());
+            }
+            Self::Scrape {
+                connection_id,
+                transaction_id,
+                info_hashes,
+            } => {
+                w.extend(connection_id.to_be_bytes());
+                w.extend(ACTION_SCRAPE.to_be_bytes());
+                w.extend(transaction_id.to_be_bytes());
+                for info_hash in info_hashes {
+                    w.extend(info_hash);
+                }
+            }
+        }
+        w
+    }
+}
+
+impl Response {
+    /// Decodes a response. `family` determines the size of peer entries in announce responses.
+    pub fn parse(packet: &[u8], family: AddressFamily) -> Result<Self, UdpPacketError> {
+        let mut r = Reader::new(packet, 8)?;
+        let action = r.u32();
+        let transaction_id = r.u32();
+
+        match action {
+            ACTION_CONNECT => {
+                r.require(16)?;
+                Ok(Self::Connect {
+                    transaction_id,
+                    connection_id: r.u64(),
+                })
+            }
+            ACTION_ANNOUNCE => {
+                r.require(20)?;
+                let interval = r.u32();
+                let leechers = r.u32();
+                let seeders = r.u32();
+                let peers = match family {
+                    AddressFamily::V4 => r.entries(6, |r| {
+                        let ip = Ipv4Addr::from(r.array::<4>());
+                        SocketAddr::V4(SocketAddrV4::new(ip, r.u16()))
+                    })?,
+                    AddressFamily::V6 => r.entries(18, |r| {
+                        let ip = Ipv6Addr::from(r.array::<16>());
+                        SocketAddr::V6(SocketAddrV6::new(ip, r.u16(), 0, 0))
+                    })?,
+                };
+                Ok(Self::Announce {
+                    transaction_id,
+                    interval,
+                    leechers,
+                    seeders,
+                    peers,
+                })
+            }
+            ACTION_SCRAPE => {
+                let torrents = r.entries(12, |r| ScrapeStats {
+                    seeders: r.u32(),
+                    completed: r.u32(),
+                    leechers: r.u32(),
+                })?;
+                Ok(Self::Scrape {
+                    transaction_id,
+                    torrents,
+                })
+            }
+            ACTION_ERROR => Ok(Self::Error {
+                transaction_id,
+                message: String::from_utf8_lossy(r.rest()).into_owned(),
+            }),
+            action => Err(UdpPacketError::UnknownAction(action)),
+        }
+    }
+
+    /// Encodes the response. Announce responses must not mix IPv4 and IPv6 peers, as there is
+    /// no way to tell them apart on the wire; IPv4-mapped IPv6 addresses can be used instead.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = Vec::with_capacity(20);
+        match self {
+            Self::Connect {
+                transaction_id,
+                connection_id,
+            } => {
+                w.extend(ACTION_CONNECT.to_be_bytes());
+                w.extend(transaction_id.to_be_bytes());
+                w.extend(connection_id.to_be_bytes());
+            }
+            Self::Announce {
+                transaction_id,
+                interval,
+                leechers,
+                seeders,
+                peers,
+            } => {
+                w.extend(ACTION_ANNOUNCE.to_be_bytes());
+                w.extend(transaction_id.to_be_bytes());
+                w.extend(interval.to_be_bytes());
+                w.extend(leechers.to_be_bytes());
+                w.extend(seeders.to_be_bytes());
+                for peer in peers {
+                    match peer {
+                        SocketAddr::V4(peer) => w.extend(peer.ip().octets()),
+                        SocketAddr::V6(peer) => w.extend(peer.ip().octets()),
+                    }
+                    w.extend(peer.port().to_be_bytes());
+                }
+            }
+            Self::Scrape {
+                transaction_id,
+                torrents,
+            } => {
+                w.extend(ACTION_SCRAPE.to_be_bytes());
+                w.extend(transaction_id.to_be_bytes());
+                for torrent in torrents {
+                    w.extend(torrent.seeders.to_be_bytes());
+                    w.extend(torrent.completed.to_be_bytes());
+                    w.extend(torrent.leechers.to_be_bytes());
+                }
+            }
+            Self::Error {
+                transaction_id,
+                message,
+            } => {
+                w.extend(ACTION_ERROR.to_be_bytes());
+                w.extend(transaction_id.to_be_bytes());
+                w.extend(message.as_bytes());
+            }
+        }
+        w
+    }
+}
+
+/// Reads big-endian fields. Lengths are checked upfront with `require`, so the accessors can't
+/// run out of bytes.
+struct Reader<'a> {
+    packet: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(packet: &'a [u8], min_len: usize) -> Result<Self, UdpPacketError> {
+        let reader = Self { packet, pos: 0 };
+        reader.require(min_len)?;
+        Ok(reader)
+    }
+
+    fn require(&self, len: usize) -> Result<(), UdpPacketError> {
+        if self.packet.len() < len {
+            return Err(UdpPacketError::Truncated {
+                expected: len,
+                actual: self.packet.len(),
+            });
+        }
+        Ok(())
+    }
+
+    fn array<const N: usize>(&mut self) -> [u8; N] {
+        let bytes = self.packet[self.pos..self.pos + N]
+            .try_into()
+            .expect("length checked by `require`");
+        self.pos += N;
+        bytes
+    }
+
+    fn u16(&mut self) -> u16 {
+        u16::from_be_bytes(self.array())
+    }
+
+    fn u32(&mut self) -> u32 {
+        u32::from_be_bytes(self.array())
+    }
+
+    fn u64(&mut self) -> u64 {
+        u64::from_be_bytes(self.array())
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        let rest = &self.packet[self.pos..];
+        self.pos = self.packet.len();
+        rest
+    }
+
+    /// Reads fixed-size entries until the end of the packet.
+    fn entries<T>(
+        &mut self,
+        size: usize,
+        mut read: impl FnMut(&mut Self) -> T,
+    ) -> Result<Vec<T>, UdpPacketError> {
+        let remainder = (self.packet.len() - self.pos) % size;
+        if remainder != 0 {
+            return Err(UdpPacketError::Truncated {
+                expected: self.packet.len() + size - remainder,
+                actual: self.packet.len(),
+            });
+        }
+        let mut entries = Vec::with_capacity((self.packet.len() - self.pos) / size);
+        while self.pos < self.packet.len() {
+            entries.push(read(self));
+        }
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn announce() -> AnnounceRequest {
+        AnnounceRequest {
+            connection_id: 0x0102_0304_0506_0708,
+            transaction_id: 0x0a0b_0c0d,
+            info_hash: [0xab; 20],
+            peer_id: PeerId::from(b"-qB46A0-k8hj0wgej6ch"),
+            downloaded: 1,
+            left: 2,
+            uploaded: 3,
+            event: Some(Event::Started),
+            ip: 0,
+            key: 4,
+            num_want: -1,
+            port: 6881,
+        }
+    }
+
+    #[test]
+    fn connect() {
+        let request = Request::Connect {
+            transaction_id: 0x1234,
+        };
+        let bytes = request.to_bytes();
+        assert_eq!(
+            bytes,
+            b"\x00\x00\x04\x17\x27\x10\x19\x80\x00\x00\x00\x00\x00\x00\x12\x34"
+        );
+        assert_eq!(Request::parse(&bytes), Ok(request));
+
+        let mut bad = bytes.clone();
+        bad[0] = 1;
+        assert_eq!(
+            Request::parse(&bad),
+            Err(UdpPacketError::BadProtocolId(0x0100_0417_2710_1980))
+        );
+
+        let response = Response::Connect {
+            transaction_id: 0x1234,
+            connection_id: 42,
+        };
+        let bytes = response.to_bytes();
+        assert_eq!(Response::parse(&bytes, AddressFamily::V4), Ok(response));
+    }
+
+    #[test]
+    fn announce_request() {
+        let request = Request::Announce(announce());
+        let mut bytes = request.to_bytes();
+        assert_eq!(bytes.len(), 98);
+        assert_eq!(&bytes[36..56], b"-qB46A0-k8hj0wgej6ch");
+        assert_eq!(&bytes[80..84], &[0, 0, 0, 2]);
+        assert_eq!(Request::parse(&bytes), Ok(request.clone()));
+
+        // BEP 41 extensions are ignored
+        bytes.extend(b"\x02\x05/test");
+        assert_eq!(Request::parse(&bytes), Ok(request));
+
+        assert_eq!(
+            Request::parse(&bytes[..97]),
+            Err(UdpPacketError::Truncated {
+                expected: 98,
+                actual: 97
+            })
+        );
+        bytes[83] = 4;
+        assert!(matches!(
+            Request::parse(&bytes),
+            Ok(Request::Announce(AnnounceRequest {
+                event: Some(Event::Paused),
+                ..
+            }))
+        ));
+        bytes[83] = 5;
+        assert_eq!(Request::parse(&bytes), Err(UdpPacketError::UnknownEvent(5)));
+    }
+
+    #[test]
+    fn announce_response() {
+        let response = Response::Announce {
+            transaction_id: 1,
+            interval: 1800,
+            leechers: 2,
+            seeders: 3,
+            peers: vec![
+                "127.0.0.1:6881".parse().unwrap(),
+                "10.0.0.1:1".parse().unwrap(),
+            ],
+        };
+        let bytes = response.to_bytes();
+        assert_eq!(bytes.len(), 20 + 2 * 6);
+        assert_eq!(Response::parse(&bytes, AddressFamily::V4), Ok(response));
+        assert_eq!(
+            Response::parse(&bytes, AddressFamily::V6),
+            Err(UdpPacketError::Truncated {
+                expected: 38,
+                actual: 32
+            })
+        );
+
+        let response = Response::Announce {
+            transaction_id: 1,
+            interval: 1800,
+            leechers: 0,
+            seeders: 1,
+            peers: vec!["[::1]:6881".parse().unwrap()],
+        };
+        let bytes = response.to_bytes();
+        assert_eq!(Response::parse(&bytes, AddressFamily::V6), Ok(response));
+    }
+
+    #[test]
+    fn scrape() {
+        let request = Request::Scrape {
+            connection_id: 1,
+            transaction_id: 2,
+            info_hashes: vec![[1; 20], [2; 20]],
+        };
+        let bytes = request.to_bytes();
+        assert_eq!(Request::parse(&bytes), Ok(request));
+        assert_eq!(
+            Request::parse(&bytes[..50]),
+            Err(UdpPacketError::Truncated {
+                expected: 56,
+                actual: 50
+            })
+        );
+
+        let response = Response::Scrape {
+            transaction_id: 2,
+            torrents: vec![ScrapeStats {
+                seeders: 1,
+                completed: 2,
+                leechers: 3,
+            }],
+        };
+        let bytes = response.to_bytes();
+        assert_eq!(Response::parse(&bytes, AddressFamily::V4), Ok(response));
+    }
+
+    #[test]
+    fn error_response() {
+        let response = Response::Error {
+            transaction_id: 7,
+            message: "unregistered torrent".to_owned(),
+        };
+        let bytes = response.to_bytes();
+        assert_eq!(Response::parse(&bytes, AddressFamily::V4), Ok(response));
+        assert_eq!(
+            Response::parse(&[0, 0, 0, 9, 0, 0, 0, 0], AddressFamily::V4),
+            Err(UdpPacketError::UnknownAction(9))
+        );
+        assert_eq!(
+            Response::parse(&[0, 0, 0], AddressFamily::V4),
+            Err(UdpPacketError::Truncated {
+                expected: 8,
+                actual: 3
+            })
+        );
+    }
+}
@@ -0,0 +1,163 @@
+//! Percent-encoding peer IDs for HTTP tracker announces, and tolerant decoding of what real
+//! clients send.
+
+use crate::errors::{BadPeerIdLengthError, UrlDecodeError};
+use crate::PeerId;
+
+impl PeerId {
+    /// Percent-encodes the peer ID for the `peer_id` query parameter. Unreserved characters
+    /// (`A-Z`, `a-z`, `0-9`, `-`, `.`, `_`, `~`) are kept as is, everything else becomes `%XX`
+    /// with uppercase hex, as RFC 3986 recommends.
+    ///
+    /// ```
+    /// # use tdyne_peer_id::PeerId;
+    /// let peer_id = PeerId::from(b"-TR4050-k8hj0wge \x00\xff/");
+    /// assert_eq!(peer_id.to_url_encoded(), "-TR4050-k8hj0wge%20%00%FF%2F");
+    /// ```
+    pub fn to_url_encoded(&self) -> String {
+        let mut encoded = String::with_capacity(60);
+        percent_encode(&self.0, &mut encoded);
+        encoded
+    }
+
+    /// Decodes a percent-encoded `peer_id` query parameter, tolerating the quirks of real
+    /// clients:
+    ///
+    /// * hex digits in either case,
+    /// * characters that should have been escaped but weren't, including non-ASCII bytes,
+    /// * `+` for a space, as in HTML forms,
+    /// * double encoding, such as `%252D` for `-`, if decoding once doesn't give 20 bytes but
+    ///   decoding twice does.
+    ///
+    /// A `%` that isn't followed by two hex digits is an error.
+    ///
+    /// ```
+    /// # use tdyne_peer_id::PeerId;
+    /// let expected = PeerId::from(b"-TR4050-k8hj0wge \x00\xff/");
+    /// for encoded in [
+    ///     "-TR4050-k8hj0wge%20%00%FF%2F",
+    ///     "-TR4050-k8hj0wge+%00%ff/",
+    ///     "%252DTR4050-k8hj0wge%2520%2500%25FF%252F",
+    /// ] {
+    ///     assert_eq!(PeerId::from_url_encoded(encoded), Ok(expected));
+    /// }
+    /// ```
+    pub fn from_url_encoded(input: impl AsRef<[u8]>) -> Result<Self, UrlDecodeError> {
+        decode_20_bytes(input.as_ref()).map(Self)
+    }
+}
+
+/// Decodes a percent-encoded 20-byte value, such as a peer ID or an info-hash, with the
+/// tolerance described in [`PeerId::from_url_encoded`].
+pub(crate) fn decode_20_bytes(input: &[u8]) -> Result<[u8; 20], UrlDecodeError> {
+    let decoded = percent_decode(input)?;
+    if let Ok(bytes) = decoded.as_slice().try_into() {
+        return Ok(bytes);
+    }
+    // a double-encoded value decodes into a percent-encoded one
+    if let Some(bytes) = percent_decode(&decoded)
+        .ok()
+        .and_then(|twice| twice.as_slice().try_into().ok())
+    {
+        return Ok(bytes);
+    }
+    Err(UrlDecodeError::Length(BadPeerIdLengthError(decoded.len())))
+}
+
+/// Appends `bytes` to `out`, keeping unreserved characters and escaping everything else.
+pub(crate) fn percent_encode(bytes: &[u8], out: &mut String) {
+    fn hex_digit(n: u8) -> char {
+        char::from_digit(u32::from(n), 16)
+            .expect("hex digit")
+            .to_ascii_uppercase()
+    }
+
+    for &b in bytes {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~') {
+            out.push(char::from(b));
+        } else {
+            out.push('%');
+            out.push(hex_digit(b >> 4));
+            out.push(hex_digit(b & 0xf));
+        }
+    }
+}
+
+/// Decodes `%XX` escapes in either case and `+` as a space, passing everything else through.
+pub(crate) fn percent_decode(input: &[u8]) -> Result<Vec<u8>, UrlDecodeError> {
+    let mut decoded = Vec::with_capacity(input.len());
+    let mut pos = 0;
+    while let Some(&b) = input.get(pos) {
+        match b {
+            b'%' => {
+                let escape = input
+                    .get(pos + 1..pos + 3)
+                    .ok_or(UrlDecodeError::TruncatedEscape { position: pos })?;
+                let digit = |d: u8| char::from(d).to_digit(16);
+                let (Some(high), Some(low)) = (digit(escape[0]), digit(escape[1])) else {
+                    return Err(UrlDecodeError::InvalidEscape { position: pos });
+                };
+                decoded.push((high << 4 | low) as u8);
+                pos += 3;
+            }
+            b'+' => {
+                decoded.push(b' ');
+                pos += 1;
+            }
+            _ => {
+                decoded.push(b);
+                pos += 1;
+            }
+        }
+    }
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn round_trip() {
+        let mut bytes = [0; 20];
+        for start in (0..=255u8).step_by(20) {
+            for (i, b) in bytes.iter_mut().enumerate() {
+                *b = start.wrapping_add(i as u8);
+            }
+            let peer_id = PeerId::from(bytes);
+            assert_eq!(
+                PeerId::from_url_encoded(peer_id.to_url_encoded()),
+                Ok(peer_id)
+            );
+        }
+    }
+
+    #[test]
+    fn unescaped_bytes() {
+        // some clients send raw bytes, which aren't even valid UTF-8
+        let raw = b"-TR4050-k8hj0wge\xff\x00?&";
+        assert_eq!(PeerId::from_url_encoded(raw), Ok(PeerId::from(raw)));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            PeerId::from_url_encoded("-TR4050-%zz"),
+            Err(UrlDecodeError::InvalidEscape { position: 8 })
+        );
+        assert_eq!(
+            PeerId::from_url_encoded("-TR4050-%2"),
+            Err(UrlDecodeError::TruncatedEscape { position: 8 })
+        );
+        assert_eq!(
+            PeerId::from_url_encoded("-TR4050-%2D"),
+            Err(UrlDecodeError::Length(BadPeerIdLengthError(9)))
+        );
+        // a literal `%` in a single-encoded peer ID isn't mistaken for double encoding
+        assert_eq!(
+            PeerId::from_url_encoded("-TR4050-%25%25%25%25%25%25%25%25%25%25%25%25"),
+            Ok(PeerId::from(b"-TR4050-%%%%%%%%%%%%"))
+        );
+    }
+}
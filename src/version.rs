@@ -0,0 +1,625 @@
+//! Client versions encoded in peer IDs.
+//!
+//! Clients don't agree on how to encode their version in the four Azureus version characters,
+//! so decoding depends on the client. [`PeerId::client_version`] picks the right
+//! [`VersionEncoding`] from the [registry].
+
+use crate::errors::{ParseVersionError, ParseVersionReqError};
+use crate::registry;
+use crate::style::{AzureusPeerId, MainlinePeerId, ShadowPeerId, Style};
+use crate::PeerId;
+use std::fmt;
+use std::str::FromStr;
+
+/// A client version decoded from a peer ID.
+///
+/// Peer IDs have room for at most four meaningful version components, so that's what is stored.
+/// Components that the client doesn't encode are zero.
+///
+/// Versions are ordered by their components first and by [`Channel`] second, so a beta sorts
+/// before the stable release with the same number.
+///
+/// ```
+/// # use tdyne_peer_id::version::{Channel, ClientVersion};
+/// let beta = ClientVersion::new(4, 0, 0, 0).with_channel(Channel::Beta);
+/// assert!(beta < ClientVersion::new(4, 0, 0, 0));
+/// assert!(beta > ClientVersion::new(3, 0, 0, 0));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct ClientVersion {
+    /// The first version component.
+    pub major: u16,
+    /// The second version component.
+    pub minor: u16,
+    /// The third version component.
+    pub patch: u16,
+    /// The fourth version component, rarely used.
+    pub build: u16,
+    /// Release channel. Most clients don't encode it, so it's usually [`Channel::Stable`].
+    pub channel: Channel,
+}
+
+/// Release channel of a client build.
+///
+/// Ordered from the least to the most stable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Channel {
+    /// Development or nightly builds, and alphas.
+    Dev,
+    /// Beta builds and release candidates.
+    Beta,
+    /// Regular releases.
+    #[default]
+    Stable,
+}
+
+/// How a client encodes its version in the four Azureus version characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum VersionEncoding {
+    /// Four components, one character each, with `0-9` standing for themselves and `A-Z` (or
+    /// `a-z`) for 10 to 35. This covers plain decimal digits (Azureus), hex digits
+    /// (libtorrent) and letters for components above 9 (qBittorrent). The default for unknown
+    /// clients.
+    Alphanumeric,
+    /// µTorrent: three hex components followed by a release tag, `B` for betas and `A` for
+    /// alphas. For example, `-UT355B-` is 3.5.5 beta.
+    MicroTorrent,
+    /// Transmission: `0.xx` versions are `00xx`, `1.xx` to `3.xx` are `xyyT`, `4.x.y` and later
+    /// are `xyzT`, where `T` is `0` for releases, `B` for betas and `X` or `Z` for development
+    /// builds. For example, `-TR294Z-` is 2.94 dev and `-TR400B-` is 4.0.0 beta.
+    Transmission,
+    /// WebTorrent: two decimal digits for the major version and two for the minor, the patch
+    /// version is not encoded. For example, `-WW0109-` is 1.9.
+    WebTorrent,
+    /// Deluge: like [`Alphanumeric`](Self::Alphanumeric) for 1.x, while 2.x and later put a
+    /// release tag in place of the fourth component: `s` for releases, `D` for development
+    /// builds, `a` for alphas, `b` for betas and `r` for release candidates. For example,
+    /// `-DE211s-` is 2.1.1 and `-DE200D-` is 2.0.0 dev.
+    Deluge,
+}
+
+impl ClientVersion {
+    /// Creates a stable version from its components.
+    pub const fn new(major: u16, minor: u16, patch: u16, build: u16) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+            build,
+            channel: Channel::Stable,
+        }
+    }
+
+    /// Returns the same version on a different release channel.
+    pub const fn with_channel(self, channel: Channel) -> Self {
+        Self { channel, ..self }
+    }
+
+    /// Decodes the four Azureus version characters according to `encoding`.
+    pub(crate) fn from_azureus(azureus: &AzureusPeerId, encoding: VersionEncoding) -> Self {
+        // `AzureusPeerId` guarantees ASCII alphanumeric version characters
+        let mut digits = [0; 4];
+        for (digit, c) in digits.iter_mut().zip(azureus.version().chars()) {
+            *digit = c.to_digit(36).expect("alphanumeric version") as u16;
+        }
+        let tag = azureus.version().as_bytes()[3];
+        let [a, b, c, d] = digits;
+
+        match encoding {
+            VersionEncoding::Alphanumeric => Self::new(a, b, c, d),
+            VersionEncoding::MicroTorrent => {
+                let channel = match tag {
+                    b'A' => Channel::Dev,
+                    b'B' => Channel::Beta,
+                    _ => Channel::Stable,
+                };
+                Self::new(a, b, c, 0).with_channel(channel)
+            }
+            VersionEncoding::Transmission => {
+                if a == 0 {
+                    return Self::new(0, c * 10 + d, 0, 0);
+                }
+                let channel = match tag {
+                    b'X' | b'Z' => Channel::Dev,
+                    b'B' => Channel::Beta,
+                    _ => Channel::Stable,
+                };
+                let version = if a < 4 {
+                    Self::new(a, b * 10 + c, 0, 0)
+                } else {
+                    Self::new(a, b, c, 0)
+                };
+                version.with_channel(channel)
+            }
+            VersionEncoding::WebTorrent => Self::new(a * 10 + b, c * 10 + d, 0, 0),
+            VersionEncoding::Deluge if a < 2 => Self::new(a, b, c, d),
+            VersionEncoding::Deluge => {
+                let channel = match tag {
+                    b'D' | b'a' => Channel::Dev,
+                    b'b' | b'r' => Channel::Beta,
+                    _ => Channel::Stable,
+                };
+                Self::new(a, b, c, 0).with_channel(channel)
+            }
+        }
+    }
+
+    /// Encodes the version into four Azureus version characters, the inverse of `from_azureus`.
+    /// Returns `None` if the version can't be represented exactly: a component is too large, the
+    /// encoding has no room for it, or the encoding can't express the release channel.
+    pub(crate) fn to_azureus(self, encoding: VersionEncoding) -> Option<[u8; 4]> {
+        fn digit(n: u16) -> Option<u8> {
+            char::from_digit(u32::from(n), 36).map(|c| c.to_ascii_uppercase() as u8)
+        }
+
+        let Self {
+            major,
+            minor,
+            patch,
+            build,
+            channel,
+        } = self;
+        match encoding {
+            VersionEncoding::Alphanumeric => {
+                if channel != Channel::Stable {
+                    return None;
+                }
+                Some([digit(major)?, digit(minor)?, digit(patch)?, digit(build)?])
+            }
+            VersionEncoding::MicroTorrent => {
+                let tag = match channel {
+                    Channel::Dev => b'A',
+                    Channel::Beta => b'B',
+                    Channel::Stable => b'0',
+                };
+                if build != 0 {
+                    return None;
+                }
+                Some([digit(major)?, digit(minor)?, digit(patch)?, tag])
+            }
+            VersionEncoding::Transmission => {
+                let tag = match channel {
+                    Channel::Dev => b'Z',
+                    Channel::Beta => b'B',
+                    Channel::Stable => b'0',
+                };
+                match major {
+                    0 if minor < 100 && patch == 0 && build == 0 && channel == Channel::Stable => {
+                        Some([b'0', b'0', digit(minor / 10)?, digit(minor % 10)?])
+                    }
+                    1..=3 if minor < 100 && patch == 0 && build == 0 => {
+                        Some([digit(major)?, digit(minor / 10)?, digit(minor % 10)?, tag])
+                    }
+                    4.. if build == 0 => Some([digit(major)?, digit(minor)?, digit(patch)?, tag]),
+                    _ => None,
+                }
+            }
+            VersionEncoding::WebTorrent => {
+                if major >= 100 || minor >= 100 || patch != 0 || build != 0 {
+                    return None;
+                }
+                if channel != Channel::Stable {
+                    return None;
+                }
+                Some([
+                    digit(major / 10)?,
+                    digit(major % 10)?,
+                    digit(minor / 10)?,
+                    digit(minor % 10)?,
+                ])
+            }
+            VersionEncoding::Deluge if major < 2 => self.to_azureus(VersionEncoding::Alphanumeric),
+            VersionEncoding::Deluge => {
+                let tag = match channel {
+                    Channel::Dev => b'D',
+                    Channel::Beta => b'b',
+                    Channel::Stable => b's',
+                };
+                if build != 0 {
+                    return None;
+                }
+                Some([digit(major)?, digit(minor)?, digit(patch)?, tag])
+            }
+        }
+    }
+
+    /// Takes up to the first four Shadow version parts. The fifth one is very rarely used and is
+    /// still available from [`ShadowPeerId::version_parts`].
+    pub(crate) fn from_shadow(shadow: &ShadowPeerId) -> Self {
+        let mut parts = [0; 4];
+        for (part, &p) in parts.iter_mut().zip(shadow.version_parts()) {
+            *part = u16::from(p);
+        }
+        let [major, minor, patch, build] = parts;
+        Self::new(major, minor, patch, build)
+    }
+
+    pub(crate) fn from_mainline(mainline: &MainlinePeerId) -> Self {
+        Self::new(mainline.major(), mainline.minor(), mainline.patch(), 0)
+    }
+}
+
+/// Renders the version as `major.minor.patch`, adding `.build` if it's not zero and `-beta` or
+/// `-dev` for non-stable channels.
+///
+/// ```
+/// # use tdyne_peer_id::version::{Channel, ClientVersion};
+/// assert_eq!(ClientVersion::new(4, 2, 5, 0).to_string(), "4.2.5");
+/// assert_eq!(ClientVersion::new(2, 0, 6, 1).to_string(), "2.0.6.1");
+/// let beta = ClientVersion::new(4, 0, 0, 0).with_channel(Channel::Beta);
+/// assert_eq!(beta.to_string(), "4.0.0-beta");
+/// ```
+impl fmt::Display for ClientVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        if self.build != 0 {
+            write!(f, ".{}", self.build)?;
+        }
+        match self.channel {
+            Channel::Dev => f.write_str("-dev"),
+            Channel::Beta => f.write_str("-beta"),
+            Channel::Stable => Ok(()),
+        }
+    }
+}
+
+/// Parses the format produced by [`Display`]: one to four numbers separated by dots, with an
+/// optional `-beta` or `-dev` suffix. Missing components are zero.
+///
+/// [`Display`]: std::fmt::Display
+///
+/// ```
+/// # use tdyne_peer_id::version::{Channel, ClientVersion};
+/// assert_eq!("2.94".parse(), Ok(ClientVersion::new(2, 94, 0, 0)));
+/// let beta = ClientVersion::new(4, 0, 0, 0).with_channel(Channel::Beta);
+/// assert_eq!("4.0.0-beta".parse(), Ok(beta));
+/// ```
+impl FromStr for ClientVersion {
+    type Err = ParseVersionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseVersionError(s.to_owned());
+
+        let (numbers, channel) = match s.split_once('-') {
+            None => (s, Channel::Stable),
+            Some((numbers, "beta")) => (numbers, Channel::Beta),
+            Some((numbers, "dev")) => (numbers, Channel::Dev),
+            Some(_) => return Err(err()),
+        };
+
+        let mut parts = [0; 4];
+        for (i, number) in numbers.split('.').enumerate() {
+            let part = parts.get_mut(i).ok_or_else(err)?;
+            // `u16::from_str` accepts a leading `+`, which isn't a version
+            if !number.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(err());
+            }
+            *part = number.parse().map_err(|_| err())?;
+        }
+
+        let [major, minor, patch, build] = parts;
+        Ok(Self::new(major, minor, patch, build).with_channel(channel))
+    }
+}
+
+/// Comparison operator of a [`Comparator`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Op {
+    /// `=`
+    Exact,
+    /// `>`
+    Greater,
+    /// `>=`
+    GreaterEq,
+    /// `<`
+    Less,
+    /// `<=`
+    LessEq,
+}
+
+/// A single condition of a [`VersionReq`], such as `>= 2.9`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Comparator {
+    /// How to compare.
+    pub op: Op,
+    /// What to compare with.
+    pub version: ClientVersion,
+}
+
+impl Comparator {
+    /// Checks whether `version` satisfies the condition.
+    pub fn matches(&self, version: &ClientVersion) -> bool {
+        match self.op {
+            Op::Exact => version == &self.version,
+            Op::Greater => version > &self.version,
+            Op::GreaterEq => version >= &self.version,
+            Op::Less => version < &self.version,
+            Op::LessEq => version <= &self.version,
+        }
+    }
+}
+
+impl fmt::Display for Comparator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self.op {
+            Op::Exact => "=",
+            Op::Greater => ">",
+            Op::GreaterEq => ">=",
+            Op::Less => "<",
+            Op::LessEq => "<=",
+        };
+        write!(f, "{} {}", op, self.version)
+    }
+}
+
+/// A set of [`Comparator`]s that a [`ClientVersion`] has to satisfy all at once, such as
+/// `>= 2.9, < 3.0`. An empty requirement matches any version.
+///
+/// Comparisons follow the [`ClientVersion`] ordering, so `< 3.0` lets `3.0.0-beta` through,
+/// while `>= 3.0` doesn't.
+///
+/// ```
+/// # use tdyne_peer_id::version::{ClientVersion, VersionReq};
+/// let req: VersionReq = ">= 2.9, < 3.0".parse().unwrap();
+/// assert!(req.matches(&ClientVersion::new(2, 94, 0, 0)));
+/// assert!(!req.matches(&ClientVersion::new(3, 0, 0, 0)));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct VersionReq {
+    /// All the conditions, joined with a logical AND.
+    pub comparators: Vec<Comparator>,
+}
+
+impl VersionReq {
+    /// Checks whether `version` satisfies every comparator.
+    pub fn matches(&self, version: &ClientVersion) -> bool {
+        self.comparators.iter().all(|c| c.matches(version))
+    }
+}
+
+impl FromStr for VersionReq {
+    type Err = ParseVersionReqError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim().is_empty() {
+            return Ok(Self::default());
+        }
+
+        let comparators = s
+            .split(',')
+            .map(|comparator| {
+                let err = || ParseVersionReqError(comparator.trim().to_owned());
+                let comparator = comparator.trim();
+                // two-character operators go first so that `>=` isn't read as `>`
+                let (op, version) = [
+                    (">=", Op::GreaterEq),
+                    ("<=", Op::LessEq),
+                    (">", Op::Greater),
+                    ("<", Op::Less),
+                    ("=", Op::Exact),
+                ]
+                .into_iter()
+                .find_map(|(prefix, op)| Some((op, comparator.strip_prefix(prefix)?)))
+                .ok_or_else(err)?;
+                let version = version.trim().parse().map_err(|_| err())?;
+                Ok(Comparator { op, version })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { comparators })
+    }
+}
+
+impl fmt::Display for VersionReq {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, comparator) in self.comparators.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", comparator)?;
+        }
+        Ok(())
+    }
+}
+
+/// A version requirement for a particular Azureus-style client, for example for enforcing
+/// minimum client versions on a private tracker.
+///
+/// Only Azureus-style peer IDs are matched: Shadow and Mainline client letters overlap, so they
+/// can't be told apart by code alone.
+///
+/// ```
+/// # use tdyne_peer_id::PeerId;
+/// # use tdyne_peer_id::version::ClientReq;
+/// let req = ClientReq::new("TR", ">= 2.9, < 3.0".parse().unwrap());
+/// assert!(req.matches(&PeerId::from(b"-TR2940-k8hj0wgej6ch")));
+/// assert!(!req.matches(&PeerId::from(b"-TR3000-k8hj0wgej6ch")));
+/// assert!(!req.matches(&PeerId::from(b"-qB2940-k8hj0wgej6ch")));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ClientReq {
+    /// Two-character client code, such as `TR`.
+    pub code: String,
+    /// Requirement for the version decoded with [`PeerId::client_version`].
+    pub req: VersionReq,
+}
+
+impl ClientReq {
+    /// Creates a requirement for the client with the given code.
+    pub fn new(code: impl Into<String>, req: VersionReq) -> Self {
+        Self {
+            code: code.into(),
+            req,
+        }
+    }
+
+    /// Checks that the peer ID belongs to the client and that its version satisfies the
+    /// requirement.
+    pub fn matches(&self, peer_id: &PeerId) -> bool {
+        let Some(azureus) = peer_id.parse_azureus() else {
+            return false;
+        };
+        azureus.client_code() == self.code
+            && peer_id
+                .client_version()
+                .is_some_and(|version| self.req.matches(&version))
+    }
+}
+
+impl PeerId {
+    /// Decodes the client version, applying the client-specific encoding for Azureus-style peer
+    /// IDs. Unlike [`PeerId::client_info`], also works for clients missing from the registry,
+    /// assuming [`VersionEncoding::Alphanumeric`] for them.
+    ///
+    /// ```
+    /// # use tdyne_peer_id::PeerId;
+    /// # use tdyne_peer_id::version::{Channel, ClientVersion};
+    /// let version = PeerId::from(b"-TR400B-k8hj0wgej6ch").client_version().unwrap();
+    /// assert_eq!(version, ClientVersion::new(4, 0, 0, 0).with_channel(Channel::Beta));
+    /// assert_eq!(version.to_string(), "4.0.0-beta");
+    /// ```
+    pub fn client_version(&self) -> Option<ClientVersion> {
+        Some(match self.style()? {
+            Style::Azureus(azureus) => {
+                let encoding = registry::azureus_encoding(azureus.client_code())
+                    .unwrap_or(VersionEncoding::Alphanumeric);
+                ClientVersion::from_azureus(&azureus, encoding)
+            }
+            Style::Shadow(shadow) => ClientVersion::from_shadow(&shadow),
+            Style::Mainline(mainline) => ClientVersion::from_mainline(&mainline),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn version(peer_id: &[u8; 20]) -> String {
+        PeerId::from(peer_id).client_version().unwrap().to_string()
+    }
+
+    #[test]
+    fn transmission() {
+        assert_eq!(version(b"-TR0072-k8hj0wgej6ch"), "0.72.0");
+        assert_eq!(version(b"-TR2940-k8hj0wgej6ch"), "2.94.0");
+        assert_eq!(version(b"-TR294Z-k8hj0wgej6ch"), "2.94.0-dev");
+        assert_eq!(version(b"-TR133X-k8hj0wgej6ch"), "1.33.0-dev");
+        assert_eq!(version(b"-TR400B-k8hj0wgej6ch"), "4.0.0-beta");
+        assert_eq!(version(b"-TR4050-k8hj0wgej6ch"), "4.0.5");
+    }
+
+    #[test]
+    fn micro_torrent() {
+        assert_eq!(version(b"-UT3550-k8hj0wgej6ch"), "3.5.5");
+        assert_eq!(version(b"-UT355B-k8hj0wgej6ch"), "3.5.5-beta");
+        assert_eq!(version(b"-UT1A0A-k8hj0wgej6ch"), "1.10.0-dev");
+    }
+
+    #[test]
+    fn webtorrent() {
+        assert_eq!(version(b"-WW0109-k8hj0wgej6ch"), "1.9.0");
+        assert_eq!(version(b"-WW0207-k8hj0wgej6ch"), "2.7.0");
+        assert_eq!(version(b"-WD0024-k8hj0wgej6ch"), "0.24.0");
+    }
+
+    #[test]
+    fn deluge() {
+        assert_eq!(version(b"-DE13F0-k8hj0wgej6ch"), "1.3.15");
+        assert_eq!(version(b"-DE211s-k8hj0wgej6ch"), "2.1.1");
+        assert_eq!(version(b"-DE200D-k8hj0wgej6ch"), "2.0.0-dev");
+        assert_eq!(version(b"-DE200a-k8hj0wgej6ch"), "2.0.0-dev");
+        assert_eq!(version(b"-DE200r-k8hj0wgej6ch"), "2.0.0-beta");
+    }
+
+    #[test]
+    fn alphanumeric() {
+        assert_eq!(version(b"-lt0D60-k8hj0wgej6ch"), "0.13.6");
+        assert_eq!(version(b"-qB46A0-k8hj0wgej6ch"), "4.6.10");
+        assert_eq!(version(b"-AZ2060-k8hj0wgej6ch"), "2.0.6");
+        // unknown client
+        assert_eq!(version(b"-ZZ1234-k8hj0wgej6ch"), "1.2.3.4");
+    }
+
+    #[test]
+    fn encode_round_trip() {
+        let cases: &[(&[u8; 4], VersionEncoding)] = &[
+            (b"0072", VersionEncoding::Transmission),
+            (b"294Z", VersionEncoding::Transmission),
+            (b"400B", VersionEncoding::Transmission),
+            (b"4050", VersionEncoding::Transmission),
+            (b"355B", VersionEncoding::MicroTorrent),
+            (b"3550", VersionEncoding::MicroTorrent),
+            (b"46A0", VersionEncoding::Alphanumeric),
+            (b"0109", VersionEncoding::WebTorrent),
+            (b"13F0", VersionEncoding::Deluge),
+            (b"211s", VersionEncoding::Deluge),
+            (b"200D", VersionEncoding::Deluge),
+            (b"210b", VersionEncoding::Deluge),
+        ];
+        for &(chars, encoding) in cases {
+            let mut bytes = *b"-XX0000-k8hj0wgej6ch";
+            bytes[3..7].copy_from_slice(chars);
+            let azureus = PeerId::from(bytes).parse_azureus().unwrap();
+            let version = ClientVersion::from_azureus(&azureus, encoding);
+            assert_eq!(version.to_azureus(encoding).as_ref(), Some(chars));
+        }
+    }
+
+    #[test]
+    fn encode_out_of_range() {
+        let beta = ClientVersion::new(1, 0, 0, 0).with_channel(Channel::Beta);
+        assert_eq!(beta.to_azureus(VersionEncoding::Alphanumeric), None);
+        let big = ClientVersion::new(36, 0, 0, 0);
+        assert_eq!(big.to_azureus(VersionEncoding::Alphanumeric), None);
+        let patch = ClientVersion::new(2, 94, 1, 0);
+        assert_eq!(patch.to_azureus(VersionEncoding::Transmission), None);
+        let build = ClientVersion::new(3, 5, 5, 1);
+        assert_eq!(build.to_azureus(VersionEncoding::MicroTorrent), None);
+        let patch = ClientVersion::new(1, 9, 7, 0);
+        assert_eq!(patch.to_azureus(VersionEncoding::WebTorrent), None);
+        let build = ClientVersion::new(2, 1, 1, 1);
+        assert_eq!(build.to_azureus(VersionEncoding::Deluge), None);
+    }
+
+    #[test]
+    fn ordering() {
+        let dev = ClientVersion::new(2, 94, 0, 0).with_channel(Channel::Dev);
+        let beta = ClientVersion::new(2, 94, 0, 0).with_channel(Channel::Beta);
+        let stable = ClientVersion::new(2, 94, 0, 0);
+        assert!(dev < beta && beta < stable);
+        assert!(stable < ClientVersion::new(2, 94, 0, 1).with_channel(Channel::Dev));
+    }
+
+    #[test]
+    fn parse_version() {
+        let dev = ClientVersion::new(1, 2, 3, 4).with_channel(Channel::Dev);
+        assert_eq!("1.2.3.4-dev".parse(), Ok(dev));
+        assert_eq!("7".parse(), Ok(ClientVersion::new(7, 0, 0, 0)));
+        for bad in ["", "1..2", "1.2.3.4.5", "+1.2", "1.2-rc", "70000"] {
+            assert!(bad.parse::<ClientVersion>().is_err(), "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn version_req() {
+        let req: VersionReq = ">=2.9,<3.0".parse().unwrap();
+        assert_eq!(req.to_string(), ">= 2.9.0, < 3.0.0");
+        assert_eq!(req.to_string().parse(), Ok(req.clone()));
+
+        let beta = ClientVersion::new(3, 0, 0, 0).with_channel(Channel::Beta);
+        assert!(req.matches(&beta));
+        assert!(!req.matches(&ClientVersion::new(2, 8, 0, 0)));
+
+        let exact: VersionReq = "= 4.0.0-beta".parse().unwrap();
+        assert!(exact.matches(&ClientVersion::new(4, 0, 0, 0).with_channel(Channel::Beta)));
+        assert!(!exact.matches(&ClientVersion::new(4, 0, 0, 0)));
+
+        assert!(VersionReq::default().matches(&ClientVersion::default()));
+        assert_eq!(
+            ">= 2.9, ~3".parse::<VersionReq>(),
+            Err(ParseVersionReqError("~3".to_owned()))
+        );
+    }
+}
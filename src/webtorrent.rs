@@ -0,0 +1,119 @@
+//! WebTorrent support: WebSocket tracker peer IDs and WebTorrent-style peer IDs.
+//!
+//! WebSocket trackers exchange JSON, and JSON has no byte strings, so peer IDs are sent as
+//! "binary strings": 20 characters, each a code point from U+0000 to U+00FF standing for one byte,
+//! which is what latin-1 decoding produces. Treating them as UTF-8 instead mangles every byte
+//! from `0x80` up.
+
+use crate::errors::{BadPeerIdLengthError, Latin1Error};
+use crate::PeerId;
+
+impl PeerId {
+    /// Converts the peer ID into a latin-1 "binary string", one character per byte, as sent by
+    /// WebSocket trackers.
+    ///
+    /// ```
+    /// # use tdyne_peer_id::PeerId;
+    /// let peer_id = PeerId::from(b"-WW0109-k8hj0wgej6\x00\xff");
+    /// let binary = peer_id.to_latin1();
+    /// assert_eq!(binary, "-WW0109-k8hj0wgej6\u{0}\u{ff}");
+    /// assert_eq!(binary.chars().count(), 20);
+    /// assert_eq!(PeerId::from_latin1(&binary), Ok(peer_id));
+    /// ```
+    pub fn to_latin1(&self) -> String {
+        self.0.iter().map(|&b| char::from(b)).collect()
+    }
+
+    /// Converts a latin-1 "binary string", one character per byte, into a peer ID. Fails if a
+    /// character is above U+00FF or if there aren't exactly 20 characters.
+    ///
+    /// ```
+    /// # use tdyne_peer_id::PeerId;
+    /// # use tdyne_peer_id::errors::Latin1Error;
+    /// // `ÿ` is U+00FF, while its UTF-8 encoding is 2 bytes
+    /// let peer_id = PeerId::from_latin1("-WW0109-k8hj0wgej6cÿ").unwrap();
+    /// assert_eq!(peer_id.0[19], 0xff);
+    ///
+    /// let error = PeerId::from_latin1("-WW0109-k8hj0wgej6c€").unwrap_err();
+    /// assert_eq!(error, Latin1Error::NotLatin1 { position: 19, c: '€' });
+    /// ```
+    pub fn from_latin1(input: &str) -> Result<Self, Latin1Error> {
+        let mut bytes = [0; 20];
+        let mut len = 0;
+        for (position, c) in input.chars().enumerate() {
+            let b = u8::try_from(c).map_err(|_| Latin1Error::NotLatin1 { position, c })?;
+            if let Some(slot) = bytes.get_mut(position) {
+                *slot = b;
+            }
+            len += 1;
+        }
+        if len != bytes.len() {
+            return Err(Latin1Error::Length(BadPeerIdLengthError(len)));
+        }
+        Ok(Self(bytes))
+    }
+
+    /// Checks if the peer ID was generated by WebTorrent or WebTorrent Desktop, that is if it's
+    /// an Azureus-style peer ID with the `WW` or `WD` client code.
+    ///
+    /// ```
+    /// # use tdyne_peer_id::PeerId;
+    /// let peer_id = PeerId::from(b"-WW0109-k8hj0wgej6ch");
+    /// assert!(peer_id.is_webtorrent());
+    /// assert_eq!(peer_id.client_info().unwrap().version.to_string(), "1.9.0");
+    ///
+    /// assert!(!PeerId::from(b"-TR4050-k8hj0wgej6ch").is_webtorrent());
+    /// ```
+    pub fn is_webtorrent(&self) -> bool {
+        self.parse_azureus()
+            .is_some_and(|azureus| matches!(azureus.client_code(), "WW" | "WD"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn latin1_round_trip() {
+        let mut bytes = [0; 20];
+        for (i, b) in bytes.iter_mut().enumerate() {
+            *b = (i as u8) * 13;
+        }
+        let peer_id = PeerId::from(bytes);
+        assert_eq!(PeerId::from_latin1(&peer_id.to_latin1()), Ok(peer_id));
+
+        let binary =
+            PeerId::from(b"-WW0109-\x80\x81\x82\x83\x84\x85\x86\x87\x88\x89\xfe\xff").to_latin1();
+        // every byte from 0x80 up takes two bytes in UTF-8
+        assert_eq!(binary.len(), 32);
+    }
+
+    #[test]
+    fn latin1_errors() {
+        assert_eq!(
+            PeerId::from_latin1("-WW0109-"),
+            Err(Latin1Error::Length(BadPeerIdLengthError(8)))
+        );
+        assert_eq!(
+            PeerId::from_latin1("-WW0109-k8hj0wgej6chx"),
+            Err(Latin1Error::Length(BadPeerIdLengthError(21)))
+        );
+        assert_eq!(
+            PeerId::from_latin1("\u{100}"),
+            Err(Latin1Error::NotLatin1 {
+                position: 0,
+                c: '\u{100}'
+            })
+        );
+    }
+
+    #[test]
+    fn webtorrent() {
+        let info = PeerId::from(b"-WD0024-k8hj0wgej6ch").client_info().unwrap();
+        assert_eq!(info.name, "WebTorrent Desktop");
+        assert!(PeerId::from(b"-WD0024-k8hj0wgej6ch").is_webtorrent());
+        assert!(!PeerId::from(b"WW0109-k8hj0wgej6ch-").is_webtorrent());
+    }
+}